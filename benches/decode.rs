@@ -0,0 +1,97 @@
+//! Decoding throughput of the term backends.
+//!
+//! Compare against an earlier revision by running
+//! `cargo bench --bench decode -- --save-baseline before` there and
+//! `cargo bench --bench decode -- --baseline before` here. Each backend reports in its own
+//! group, the `uncached` variants of the backends that use the IRI cache build every IRI
+//! from the lookup tables, as a baseline for the cache.
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use jelly::{
+    deserialize::Deserializer,
+    from_rdf::{LiteralKind, Term},
+    proto::{PhysicalStreamType, RdfStreamFrame},
+    serialize::{FramingPolicy, Serializer},
+    to_rdf::{SharedRdf, StringRdf, ToRdf},
+};
+
+const TRIPLES: usize = 1_000_000;
+
+/// `TRIPLES` triples about `subjects` subjects with 40 predicates from 4 namespaces, the
+/// same IRIs are used over and over unless `subjects` exceeds the name table.
+fn dataset(subjects: usize) -> Vec<RdfStreamFrame> {
+    let mut ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Triples))
+        .expect("valid options");
+    ser.set_framing_policy(FramingPolicy::Rows(256));
+    for i in 0..TRIPLES {
+        let s = Term::Iri(format!("http://example.org/resource/{}", i % subjects));
+        let p = Term::Iri(format!("http://example.org/ns{}#p{}", i % 4, i % 40));
+        let o = if i % 3 == 0 {
+            Term::Iri(format!(
+                "http://example.org/resource/{}",
+                (i * 7) % subjects
+            ))
+        } else {
+            Term::Literal(i.to_string(), LiteralKind::Simple)
+        };
+        ser.triple(&s, &p, &o).unwrap();
+    }
+    ser.finish()
+}
+
+fn decode<T: ToRdf>(frames: &[RdfStreamFrame], iri_cache: bool) -> usize {
+    let mut count = 0;
+    let mut handler = (|_: T::Triple<'_>| count += 1, |_: T::Quad<'_>| {});
+    let mut des = Deserializer::<T>::new();
+    des.set_iri_cache(iri_cache);
+    for frame in frames {
+        des.handle_frame(frame.clone(), &mut handler)
+            .expect("valid frame");
+    }
+    count
+}
+
+/// One group per backend, so each reports its own numbers. Backends that build IRIs
+/// through the IRI cache also run without it.
+fn bench<T: ToRdf>(
+    c: &mut Criterion,
+    backend: &str,
+    uses_cache: bool,
+    datasets: &[(usize, Vec<RdfStreamFrame>)],
+) {
+    let mut group = c.benchmark_group(format!("decode {}", backend));
+    group.throughput(Throughput::Elements(TRIPLES as u64));
+    group.sample_size(20);
+    let caches: &[_] = if uses_cache {
+        &[(Some("cached"), true), (Some("uncached"), false)]
+    } else {
+        &[(None, true)]
+    };
+    for (subjects, frames) in datasets {
+        for &(cache, iri_cache) in caches {
+            let id = match cache {
+                Some(cache) => BenchmarkId::new(cache, subjects),
+                None => BenchmarkId::from_parameter(subjects),
+            };
+            group.bench_with_input(id, frames, |b, f| b.iter(|| decode::<T>(f, iri_cache)));
+        }
+    }
+    group.finish();
+}
+
+fn backends(c: &mut Criterion) {
+    // Repeated IRIs, and more subjects than the name table holds
+    let datasets: Vec<_> = [1_000, 20_000]
+        .into_iter()
+        .map(|subjects| (subjects, dataset(subjects)))
+        .collect();
+    bench::<StringRdf>(c, "string", false, &datasets);
+    bench::<SharedRdf>(c, "shared", false, &datasets);
+    #[cfg(feature = "sophia")]
+    bench::<jelly::to_rdf::SophiaRdf>(c, "sophia", true, &datasets);
+    #[cfg(feature = "oxrdf")]
+    bench::<jelly::to_rdf::OxRdf>(c, "oxrdf", true, &datasets);
+}
+
+criterion_group!(benches, backends);
+criterion_main!(benches);
@@ -26,7 +26,7 @@ pub mod mf {
     use sophia_api::namespace;
     namespace! {
       "http://www.w3.org/2001/sw/DataAccess/tests/test-manifest#",
-      entries, name, action, result
+      entries, name, action, result, requires
     }
 }
 
@@ -55,6 +55,7 @@ struct BasicInfo {
     approval: String,
     action: String,
     result: Option<Vec<String>>,
+    generalized: bool,
 }
 
 fn sanitize_test_name(original: &str) -> String {
@@ -152,7 +153,16 @@ fn basic_info<T: Term + Clone>(graph: &G, subj: T) -> Option<BasicInfo> {
         .to_string();
     let subject = subj.iri()?.to_string();
 
-    let result = get_result(graph, subj);
+    let result = get_result(graph, subj.clone());
+    let generalized = graph
+        .quads_matching(
+            [subj],
+            [mf::requires],
+            [jellyt::requirementGeneralizedRdf],
+            Any,
+        )
+        .next()
+        .is_some();
 
     let to_skip = "https://w3id.org/jelly/dev/tests/rdf/from_jelly/".len();
     let (ns, id) = subject[to_skip..]
@@ -167,6 +177,7 @@ fn basic_info<T: Term + Clone>(graph: &G, subj: T) -> Option<BasicInfo> {
         result,
         id: id.to_string(),
         ns: ns.to_string(),
+        generalized,
     })
 }
 
@@ -208,10 +219,19 @@ fn setup_from_jelly_tests() {
     let path = path::absolute("./proto/test/rdf/from_jelly/manifest.ttl").unwrap();
     let location = path.to_str().unwrap();
 
+    println!("cargo::rustc-check-cfg=cfg(conformance)");
+    println!("cargo::rerun-if-changed={}", location);
+    // Without the proto submodule there are no conformance tests to generate
+    let Ok(tests_manifest) = fs::read_to_string(&path) else {
+        println!(
+            "cargo:warning=Conformance tests are skipped, {} is missing (run `git submodule update --init`)",
+            location
+        );
+        return;
+    };
+    println!("cargo::rustc-cfg=conformance");
     println!("cargo:warning=Location {}", location);
 
-    let tests_manifest = fs::read_to_string(&path).expect("Failed to read spec");
-
     let base = BaseIriRef::new(format!("file://{}", location).into_boxed_str()).unwrap();
     let parser = sophia_turtle::parser::gtrig::GTriGParser::new().with_base(Some(base));
 
@@ -259,6 +279,24 @@ fn setup_from_jelly_tests() {
                     p.action,
                     p.result.as_ref().unwrap_or(&vec![])
                 );
+
+                // oxrdf can not hold generalized statements
+                if !p.generalized {
+                    generated += &format!(
+                        r#"
+#[cfg(feature = "oxrdf")]
+#[test]
+            fn {}_{}_oxrdf() {{
+                crate::init_logger();
+                crate::test_positive_oxrdf({:?}, &{:?});
+            }}
+"#,
+                        p.id,
+                        p.name,
+                        p.action,
+                        p.result.as_ref().unwrap_or(&vec![])
+                    );
+                }
             }
         }
 
@@ -286,13 +324,16 @@ fn setup_from_jelly_tests() {
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     setup_from_jelly_tests();
-    prost_build::compile_protos(
-        &[
-            "proto/proto/rdf.proto",
-            "proto/proto/patch.proto",
-            "proto/proto/grpc.proto",
-        ],
-        &["proto/proto"],
-    )?;
+    prost_build::Config::new()
+        // Row oneofs hold the stream options next to much smaller rows
+        .enum_attribute(".", "#[allow(clippy::large_enum_variant)]")
+        .compile_protos(
+            &[
+                "proto/proto/rdf.proto",
+                "proto/proto/patch.proto",
+                "proto/proto/grpc.proto",
+            ],
+            &["proto/proto"],
+        )?;
     Ok(())
 }
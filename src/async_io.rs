@@ -0,0 +1,220 @@
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+
+use futures_core::Stream;
+use prost::Message as _;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufWriter, ReadBuf};
+
+use crate::error::FrameReadError;
+use crate::io::{ReaderLimits, VarintDecoder};
+use crate::proto::RdfStreamFrame;
+
+/// Bytes of a frame body requested from the reader at once
+const CHUNK_LEN: usize = 8 * 1024;
+
+enum State {
+    Length,
+    Body { len: u64 },
+    Done,
+}
+
+/// Reads length delimited frames from an [`AsyncRead`], the async counterpart of
+/// [`crate::FrameReader`].
+///
+/// Yields an error when the stream is corrupted or ends inside a frame, after which the
+/// stream ends. A stream that ends at a frame boundary just ends the stream.
+///
+/// The length prefix is read byte by byte, wrap unbuffered readers in a
+/// [`tokio::io::BufReader`].
+pub struct AsyncFrameReader<R> {
+    reader: R,
+    limits: ReaderLimits,
+    state: State,
+    varint: VarintDecoder,
+    start: u64,
+    offset: u64,
+    buf: Vec<u8>,
+}
+
+impl<R> AsyncFrameReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            limits: ReaderLimits::default(),
+            state: State::Length,
+            varint: VarintDecoder::default(),
+            start: 0,
+            offset: 0,
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn set_limits(&mut self, limits: ReaderLimits) {
+        self.limits = limits;
+    }
+
+    /// Number of bytes read so far.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncFrameReader<R> {
+    /// Reads the next frame, `None` at the end of the stream.
+    pub async fn next_frame(&mut self) -> Option<Result<RdfStreamFrame, FrameReadError>> {
+        std::future::poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+
+    fn poll_frame(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<RdfStreamFrame>, FrameReadError>> {
+        loop {
+            match self.state {
+                State::Done => return Poll::Ready(Ok(None)),
+                State::Length => {
+                    let mut byte = [0u8];
+                    let mut read = ReadBuf::new(&mut byte);
+                    ready!(Pin::new(&mut self.reader).poll_read(cx, &mut read)).map_err(
+                        |source| FrameReadError::Io {
+                            offset: self.offset,
+                            source,
+                        },
+                    )?;
+                    if read.filled().is_empty() {
+                        if self.offset == self.start {
+                            return Poll::Ready(Ok(None));
+                        }
+                        return Poll::Ready(Err(FrameReadError::TruncatedLength {
+                            offset: self.start,
+                        }));
+                    }
+                    self.offset += 1;
+
+                    if let Some(len) = self.varint.push(byte[0], self.start)? {
+                        self.limits.check_len(len, self.start, self.offset)?;
+                        self.buf.clear();
+                        self.state = State::Body { len };
+                    }
+                }
+                State::Body { len } => {
+                    let filled = self.buf.len();
+                    let missing = len - filled as u64;
+                    if missing == 0 {
+                        let frame = self.limits.decode_frame(&self.buf, self.start)?;
+                        self.start = self.offset;
+                        self.state = State::Length;
+                        return Poll::Ready(Ok(Some(frame)));
+                    }
+
+                    // The buffer grows with the bytes actually read instead of trusting the prefix
+                    self.buf
+                        .resize(filled + missing.min(CHUNK_LEN as u64) as usize, 0);
+                    let mut read = ReadBuf::new(&mut self.buf[filled..]);
+                    let polled = Pin::new(&mut self.reader).poll_read(cx, &mut read);
+                    let read = read.filled().len();
+                    self.buf.truncate(filled + read);
+
+                    ready!(polled).map_err(|source| FrameReadError::Io {
+                        offset: self.offset,
+                        source,
+                    })?;
+                    if read == 0 {
+                        return Poll::Ready(Err(FrameReadError::TruncatedFrame {
+                            offset: self.start,
+                            expected: len,
+                            read: filled as u64,
+                        }));
+                    }
+                    self.offset += read as u64;
+                }
+            }
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for AsyncFrameReader<R> {
+    type Item = Result<RdfStreamFrame, FrameReadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let frame = ready!(this.poll_frame(cx)).transpose();
+        if !matches!(frame, Some(Ok(_))) {
+            this.state = State::Done;
+        }
+        Poll::Ready(frame)
+    }
+}
+
+/// Writes length delimited frames to an [`AsyncWrite`], the async counterpart of
+/// [`crate::FrameWriter`].
+///
+/// Writes are buffered, call [`AsyncFrameWriter::flush`] or
+/// [`AsyncFrameWriter::shutdown`] before dropping the writer.
+pub struct AsyncFrameWriter<W> {
+    writer: BufWriter<W>,
+    buf: Vec<u8>,
+    flush_after_frame: bool,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncFrameWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_capacity(8 * 1024, writer)
+    }
+
+    pub fn with_capacity(capacity: usize, writer: W) -> Self {
+        Self {
+            writer: BufWriter::with_capacity(capacity, writer),
+            buf: Vec::new(),
+            flush_after_frame: false,
+        }
+    }
+
+    pub fn set_flush_after_frame(&mut self, flush_after_frame: bool) {
+        self.flush_after_frame = flush_after_frame;
+    }
+
+    /// Writes the frame, prefixed with its length as varint.
+    pub async fn write_frame(&mut self, frame: &RdfStreamFrame) -> std::io::Result<()> {
+        self.buf.clear();
+        frame
+            .encode_length_delimited(&mut self.buf)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        self.writer.write_all(&self.buf).await?;
+
+        if self.flush_after_frame {
+            self.writer.flush().await?;
+        }
+        Ok(())
+    }
+
+    pub async fn write_frames<I>(&mut self, frames: I) -> std::io::Result<()>
+    where
+        I: IntoIterator<Item = RdfStreamFrame>,
+    {
+        for frame in frames {
+            self.write_frame(&frame).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush().await
+    }
+
+    /// Flushes the buffer and shuts down the underlying writer, signalling the end of the
+    /// stream to the reader.
+    pub async fn shutdown(&mut self) -> std::io::Result<()> {
+        self.writer.shutdown().await
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.writer.get_ref()
+    }
+
+    /// Flushes the buffer and returns the underlying writer.
+    pub async fn into_inner(mut self) -> std::io::Result<W> {
+        self.writer.flush().await?;
+        Ok(self.writer.into_inner())
+    }
+}
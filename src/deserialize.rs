@@ -1,11 +1,17 @@
-use crate::error::{ConfigError, DeserializeError, MessageType, PhysicalStreamError, TermLocation};
-use crate::lookup::Lookup;
+use crate::error::{
+    ConfigError, DeserializeError, ErrorPosition, MessageType, PhysicalStreamError, TermKind,
+    TermLocation,
+};
+use crate::lookup::{IriCache, Lookup, LookupType};
+use std::collections::HashMap;
+use std::convert::Infallible;
 
 use crate::proto::rdf_quad::Graph;
 use crate::proto::rdf_stream_row::Row;
 use crate::proto::{
-    PhysicalStreamType, RdfDatatypeEntry, RdfNameEntry, RdfPrefixEntry, RdfQuad, RdfStreamFrame,
-    RdfStreamOptions, RdfTriple, rdf_graph_start as gs, rdf_quad as q, rdf_triple as t,
+    LogicalStreamType, PhysicalStreamType, RdfDatatypeEntry, RdfIri, RdfNameEntry, RdfPrefixEntry,
+    RdfQuad, RdfStreamFrame, RdfStreamOptions, RdfTriple, rdf_graph_start as gs, rdf_quad as q,
+    rdf_triple as t,
 };
 use crate::to_rdf::ToRdf;
 use paste::paste;
@@ -17,16 +23,25 @@ pub trait ToTerm<Term, TermType> {
 }
 
 macro_rules! implTerm {
-    ($k:path, $letter:ident, $($extra:tt)?) => {
+    ($k:path, $letter:ident, $location:ident, $($extra:tt)?) => {
         paste! {
                 impl<T: ToRdf> ToTerm<$k, T::Term> for Inner<T> {
                     #[inline]
                     fn to_term(&mut self, thing: $k) -> Result<T::Term, DeserializeError> {
                         match thing {
                             $k::[<$letter Iri>](rdf_iri) => T::iri(rdf_iri, self),
-                            $k::[<$letter Bnode>](bnode) => T::bnode(bnode, self),
-                            $k::[<$letter Literal>](rdf_literal) => T::literal(rdf_literal, self),
-                            $k::[<$letter TripleTerm>](rdf_triple) => T::term_triple($($extra)? rdf_triple, self),
+                            $k::[<$letter Bnode>](bnode) => {
+                                self.check_term(TermLocation::$location, TermKind::BlankNode)?;
+                                T::bnode(bnode, self)
+                            }
+                            $k::[<$letter Literal>](rdf_literal) => {
+                                self.check_term(TermLocation::$location, TermKind::Literal)?;
+                                T::literal(rdf_literal, self)
+                            }
+                            $k::[<$letter TripleTerm>](rdf_triple) => {
+                                self.check_term(TermLocation::$location, TermKind::TripleTerm)?;
+                                T::term_triple($($extra)? rdf_triple, self)
+                            }
                         }
                     }
                 }
@@ -34,12 +49,12 @@ macro_rules! implTerm {
     };
 }
 
-implTerm!(q::Subject, S,);
-implTerm!(q::Predicate, P,);
-implTerm!(q::Object, O,);
-implTerm!(t::Subject, S, *);
-implTerm!(t::Predicate, P, *);
-implTerm!(t::Object, O, *);
+implTerm!(q::Subject, S, Subject,);
+implTerm!(q::Predicate, P, Predicate,);
+implTerm!(q::Object, O, Object,);
+implTerm!(t::Subject, S, Subject, *);
+implTerm!(t::Predicate, P, Predicate, *);
+implTerm!(t::Object, O, Object, *);
 
 pub struct Inner<T: ToRdf> {
     pub name_table: Lookup,
@@ -53,8 +68,21 @@ pub struct Inner<T: ToRdf> {
 
     pub state: T::State,
 
+    options: RdfStreamOptions,
     physical_type: PhysicalStreamType,
+    logical_type: LogicalStreamType,
+    version: u32,
     graph_started: bool,
+    // Rows other than options were read, options at the start of a frame after them start
+    // a new stream in restart mode
+    other_rows: bool,
+
+    rdf_star: bool,
+    generalized_statements: bool,
+    settings: Settings,
+    // The term being decoded, for error positions
+    location: Option<TermLocation>,
+    iri_cache: IriCache<T::Term>,
 }
 
 impl<T: ToRdf> Inner<T> {
@@ -64,6 +92,10 @@ impl<T: ToRdf> Inner<T> {
         if physical_type == PhysicalStreamType::Unspecified {
             return Err(ConfigError::InvalidPhysicalType(physical_type));
         }
+        let logical_type = ConfigError::logical_type(options.logical_type, physical_type)?;
+        if let Some(version_error) = ConfigError::version(options.version) {
+            return Err(version_error);
+        }
         if let Some(table_error) = ConfigError::name_table(options.max_name_table_size) {
             return Result::Err(table_error);
         };
@@ -88,12 +120,112 @@ impl<T: ToRdf> Inner<T> {
 
             state: T::State::default(),
 
+            options: options.clone(),
             physical_type,
+            logical_type,
+            version: options.version,
 
             graph_started: false,
+            other_rows: false,
+
+            rdf_star: options.rdf_star,
+            generalized_statements: options.generalized_statements,
+            settings: Settings::default(),
+            location: None,
+            iri_cache: IriCache::new(options.max_name_table_size),
         })
     }
 
+    /// Whether `options` start a new stream in restart mode, see
+    /// [`Deserializer::set_stream_restart`].
+    fn restarts_stream(&self, options: &RdfStreamOptions, first_in_frame: bool) -> bool {
+        self.settings.restart_on_options
+            && (*options != self.options || (first_in_frame && self.other_rows))
+    }
+
+    /// The options the stream started with.
+    pub fn options(&self) -> &RdfStreamOptions {
+        &self.options
+    }
+
+    /// Checks an options row after the first one, only the stream name may change and the
+    /// lookup tables may grow.
+    pub fn check_repeated_options(&self, options: &RdfStreamOptions) -> Result<(), ConfigError> {
+        let active = &self.options;
+        let changes = [
+            (
+                "physical_type",
+                options.physical_type != active.physical_type,
+            ),
+            ("logical_type", options.logical_type != active.logical_type),
+            (
+                "generalized_statements",
+                options.generalized_statements != active.generalized_statements,
+            ),
+            ("rdf_star", options.rdf_star != active.rdf_star),
+            (
+                "max_name_table_size",
+                options.max_name_table_size < active.max_name_table_size,
+            ),
+            (
+                "max_prefix_table_size",
+                options.max_prefix_table_size < active.max_prefix_table_size,
+            ),
+            (
+                "max_datatype_table_size",
+                options.max_datatype_table_size < active.max_datatype_table_size,
+            ),
+            ("version", options.version != active.version),
+        ];
+        match changes.into_iter().find(|(_, changed)| *changed) {
+            Some((field, _)) => Err(ConfigError::OptionsChanged(field)),
+            None => Ok(()),
+        }
+    }
+
+    /// Grows the lookup tables to the sizes of checked repeated options.
+    fn grow_tables(&mut self, options: &RdfStreamOptions) {
+        self.name_table.grow(options.max_name_table_size);
+        self.prefix_table.grow(options.max_prefix_table_size);
+        self.datatype_table.grow(options.max_datatype_table_size);
+        self.iri_cache.grow(options.max_name_table_size);
+        self.options.max_name_table_size = options.max_name_table_size;
+        self.options.max_prefix_table_size = options.max_prefix_table_size;
+        self.options.max_datatype_table_size = options.max_datatype_table_size;
+    }
+
+    pub fn physical_type(&self) -> PhysicalStreamType {
+        self.physical_type
+    }
+
+    pub fn logical_type(&self) -> LogicalStreamType {
+        self.logical_type
+    }
+
+    /// Protocol version the stream was written with, 0 if the stream does not set it.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Checks that the stream options allow a term of `kind` in `location`.
+    #[inline]
+    pub fn check_term(
+        &self,
+        location: TermLocation,
+        kind: TermKind,
+    ) -> Result<(), DeserializeError> {
+        if self.settings.lenient {
+            return Ok(());
+        }
+        if kind == TermKind::TripleTerm && !self.rdf_star {
+            return Err(DeserializeError::RdfStarNotEnabled(location));
+        }
+        if !self.generalized_statements && !location.allows(kind) {
+            return Err(DeserializeError::GeneralizedNotEnabled { location, kind });
+        }
+        Ok(())
+    }
+
     #[inline]
     pub fn prefix_entry(&mut self, prefix: RdfPrefixEntry) -> Result<(), DeserializeError> {
         self.prefix_table.set(prefix.id, prefix.value)?;
@@ -112,13 +244,53 @@ impl<T: ToRdf> Inner<T> {
         Ok(())
     }
 
+    /// Resolves `iri` through the lookup tables, the term is built from the prefix and
+    /// name with `build` unless it was built from the same entries before, see
+    /// [`Deserializer::set_iri_cache`].
+    #[inline]
+    pub fn cached_iri(
+        &mut self,
+        iri: RdfIri,
+        build: impl FnOnce(&str, &str) -> Result<T::Term, DeserializeError>,
+    ) -> Result<T::Term, DeserializeError>
+    where
+        T::Term: Clone,
+    {
+        let prefix_id = self.prefix_table.resolve(iri.prefix_id, LookupType::Stay)?;
+        let name_id = self.name_table.resolve(iri.name_id, LookupType::Inc)?;
+        let (prefix, prefix_generation) = self.prefix_table.entry(prefix_id)?;
+        let (name, name_generation) = self.name_table.entry(name_id)?;
+
+        if !self.settings.cache_iris {
+            return build(prefix, name);
+        }
+        let ids = (prefix_id, name_id);
+        let generations = (prefix_generation, name_generation);
+        self.iri_cache
+            .get_or_try_build(ids, generations, || build(prefix, name))
+    }
+
+    /// Resolves the IRI of a namespace declaration through the lookup tables.
+    #[inline]
+    pub fn namespace_iri(&mut self, iri: RdfIri) -> Result<String, DeserializeError> {
+        self.location = Some(TermLocation::Namespace);
+        Ok(format!(
+            "{}{}",
+            self.prefix_table.get(iri.prefix_id, LookupType::Stay)?,
+            self.name_table.get(iri.name_id, LookupType::Inc)?
+        ))
+    }
+
     #[inline]
     pub fn q_graph(&mut self, sub: q::Graph) -> Result<(), DeserializeError> {
         trace!("q_graph");
         match sub {
             q::Graph::GIri(iri) => self.last_graph = Some(T::iri(iri, self)?),
             q::Graph::GBnode(str) => self.last_graph = Some(T::bnode(str, self)?),
-            q::Graph::GLiteral(literal) => self.last_graph = Some(T::literal(literal, self)?),
+            q::Graph::GLiteral(literal) => {
+                self.check_term(TermLocation::Graph, TermKind::Literal)?;
+                self.last_graph = Some(T::literal(literal, self)?)
+            }
             q::Graph::GDefaultGraph(_) => self.last_graph = None,
         }
         Ok(())
@@ -127,17 +299,21 @@ impl<T: ToRdf> Inner<T> {
     #[inline]
     pub fn triple<'a>(&'a mut self, triple: RdfTriple) -> Result<T::Triple<'a>, DeserializeError> {
         if let Some(subject) = triple.subject {
+            self.location = Some(TermLocation::Subject);
             self.last_subject = Some(self.to_term(subject)?);
         }
 
         if let Some(predicate) = triple.predicate {
+            self.location = Some(TermLocation::Predicate);
             self.last_predicate = Some(self.to_term(predicate)?);
         }
 
         if let Some(object) = triple.object {
+            self.location = Some(TermLocation::Object);
             self.last_object = Some(self.to_term(object)?);
         }
 
+        self.location = None;
         T::triple(self)
     }
 
@@ -147,38 +323,47 @@ impl<T: ToRdf> Inner<T> {
         triple: RdfTriple,
     ) -> Result<T::Quad<'a>, DeserializeError> {
         if let Some(subject) = triple.subject {
+            self.location = Some(TermLocation::Subject);
             self.last_subject = Some(self.to_term(subject)?);
         }
 
         if let Some(predicate) = triple.predicate {
+            self.location = Some(TermLocation::Predicate);
             self.last_predicate = Some(self.to_term(predicate)?);
         }
 
         if let Some(object) = triple.object {
+            self.location = Some(TermLocation::Object);
             self.last_object = Some(self.to_term(object)?);
         }
 
+        self.location = None;
         T::quad(self)
     }
 
     #[inline]
     pub fn quad<'a>(&'a mut self, quad: RdfQuad) -> Result<T::Quad<'a>, DeserializeError> {
         if let Some(subject) = quad.subject {
+            self.location = Some(TermLocation::Subject);
             self.last_subject = Some(self.to_term(subject)?);
         }
 
         if let Some(predicate) = quad.predicate {
+            self.location = Some(TermLocation::Predicate);
             self.last_predicate = Some(self.to_term(predicate)?);
         }
 
         if let Some(object) = quad.object {
+            self.location = Some(TermLocation::Object);
             self.last_object = Some(self.to_term(object)?);
         }
 
         if let Some(graph) = quad.graph {
+            self.location = Some(TermLocation::Graph);
             self.q_graph(graph)?;
         }
 
+        self.location = None;
         T::quad(self)
     }
 }
@@ -210,8 +395,17 @@ impl<T: ToRdf> Clone for Inner<T>
                 None => None
             },
             state: (&self.state).to_owned(),
+            options: self.options.clone(),
             physical_type: self.physical_type,
-            graph_started: self.graph_started
+            logical_type: self.logical_type,
+            version: self.version,
+            graph_started: self.graph_started,
+            other_rows: self.other_rows,
+            rdf_star: self.rdf_star,
+            generalized_statements: self.generalized_statements,
+            settings: self.settings,
+            location: self.location,
+            iri_cache: IriCache::new(self.options.max_name_table_size),
         }
     }
 }
@@ -219,6 +413,179 @@ impl<T: ToRdf> Clone for Inner<T>
 pub trait RdfHandler<T: ToRdf> {
     fn handle_triple<'b>(&mut self, triple: T::Triple<'b>);
     fn handle_quad<'b>(&mut self, quad: T::Quad<'b>);
+
+    /// A namespace declaration of `name` (without colon) for the full `iri`.
+    fn handle_namespace(&mut self, _name: &str, _iri: &str) {}
+
+    /// Start of a graph in a `Graphs` stream, `None` is the default graph.
+    fn handle_graph_start(&mut self, _graph: Option<&T::Term>) {}
+    /// End of the graph started last.
+    fn handle_graph_end(&mut self) {}
+
+    /// Start of a frame, before any of its rows.
+    fn handle_frame_start(&mut self, _metadata: &HashMap<String, Vec<u8>>) {}
+    /// End of a frame, after all of its rows were decoded.
+    fn handle_frame_end(&mut self) {}
+}
+
+/// A handler whose callbacks can fail, the first error stops decoding and is returned
+/// from [`Deserializer::handle_frame`] as [`DeserializeError::Handler`].
+///
+/// Every [`RdfHandler`] is a `TryRdfHandler` that never fails.
+pub trait TryRdfHandler<T: ToRdf> {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    fn try_handle_triple<'b>(&mut self, triple: T::Triple<'b>) -> Result<(), Self::Error>;
+    fn try_handle_quad<'b>(&mut self, quad: T::Quad<'b>) -> Result<(), Self::Error>;
+
+    /// See [`RdfHandler::handle_namespace`].
+    fn try_handle_namespace(&mut self, _name: &str, _iri: &str) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// See [`RdfHandler::handle_graph_start`].
+    fn try_handle_graph_start(&mut self, _graph: Option<&T::Term>) -> Result<(), Self::Error> {
+        Ok(())
+    }
+    /// See [`RdfHandler::handle_graph_end`].
+    fn try_handle_graph_end(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// See [`RdfHandler::handle_frame_start`].
+    fn try_handle_frame_start(
+        &mut self,
+        _metadata: &HashMap<String, Vec<u8>>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+    /// See [`RdfHandler::handle_frame_end`].
+    fn try_handle_frame_end(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<H: RdfHandler<T>, T: ToRdf> TryRdfHandler<T> for H {
+    type Error = Infallible;
+
+    fn try_handle_triple<'b>(&mut self, triple: T::Triple<'b>) -> Result<(), Self::Error> {
+        self.handle_triple(triple);
+        Ok(())
+    }
+
+    fn try_handle_quad<'b>(&mut self, quad: T::Quad<'b>) -> Result<(), Self::Error> {
+        self.handle_quad(quad);
+        Ok(())
+    }
+
+    fn try_handle_namespace(&mut self, name: &str, iri: &str) -> Result<(), Self::Error> {
+        self.handle_namespace(name, iri);
+        Ok(())
+    }
+
+    fn try_handle_graph_start(&mut self, graph: Option<&T::Term>) -> Result<(), Self::Error> {
+        self.handle_graph_start(graph);
+        Ok(())
+    }
+
+    fn try_handle_graph_end(&mut self) -> Result<(), Self::Error> {
+        self.handle_graph_end();
+        Ok(())
+    }
+
+    fn try_handle_frame_start(
+        &mut self,
+        metadata: &HashMap<String, Vec<u8>>,
+    ) -> Result<(), Self::Error> {
+        self.handle_frame_start(metadata);
+        Ok(())
+    }
+
+    fn try_handle_frame_end(&mut self) -> Result<(), Self::Error> {
+        self.handle_frame_end();
+        Ok(())
+    }
+}
+
+fn handler_error<E: std::error::Error + Send + Sync + 'static>(e: E) -> DeserializeError {
+    DeserializeError::Handler(Box::new(e))
+}
+
+/// Wraps a handler and collects the namespace declarations of the stream into a prefix
+/// map, later declarations of a name replace earlier ones.
+pub struct PrefixCollector<H> {
+    pub handler: H,
+    pub prefixes: HashMap<String, String>,
+}
+
+impl<H> PrefixCollector<H> {
+    pub fn new(handler: H) -> Self {
+        Self {
+            handler,
+            prefixes: HashMap::new(),
+        }
+    }
+}
+
+impl<H: RdfHandler<T>, T: ToRdf> RdfHandler<T> for PrefixCollector<H> {
+    fn handle_triple<'b>(&mut self, triple: <T as ToRdf>::Triple<'b>) {
+        self.handler.handle_triple(triple)
+    }
+
+    fn handle_quad<'b>(&mut self, quad: <T as ToRdf>::Quad<'b>) {
+        self.handler.handle_quad(quad)
+    }
+
+    fn handle_namespace(&mut self, name: &str, iri: &str) {
+        self.prefixes.insert(name.to_string(), iri.to_string());
+        self.handler.handle_namespace(name, iri)
+    }
+
+    fn handle_graph_start(&mut self, graph: Option<&T::Term>) {
+        self.handler.handle_graph_start(graph)
+    }
+
+    fn handle_graph_end(&mut self) {
+        self.handler.handle_graph_end()
+    }
+
+    fn handle_frame_start(&mut self, metadata: &HashMap<String, Vec<u8>>) {
+        self.handler.handle_frame_start(metadata)
+    }
+
+    fn handle_frame_end(&mut self) {
+        self.handler.handle_frame_end()
+    }
+}
+
+impl<H: RdfHandler<T>, T: ToRdf> RdfHandler<T> for &mut PrefixCollector<H> {
+    fn handle_triple<'b>(&mut self, triple: <T as ToRdf>::Triple<'b>) {
+        (**self).handle_triple(triple)
+    }
+
+    fn handle_quad<'b>(&mut self, quad: <T as ToRdf>::Quad<'b>) {
+        (**self).handle_quad(quad)
+    }
+
+    fn handle_namespace(&mut self, name: &str, iri: &str) {
+        (**self).handle_namespace(name, iri)
+    }
+
+    fn handle_graph_start(&mut self, graph: Option<&T::Term>) {
+        (**self).handle_graph_start(graph)
+    }
+
+    fn handle_graph_end(&mut self) {
+        (**self).handle_graph_end()
+    }
+
+    fn handle_frame_start(&mut self, metadata: &HashMap<String, Vec<u8>>) {
+        (**self).handle_frame_start(metadata)
+    }
+
+    fn handle_frame_end(&mut self) {
+        (**self).handle_frame_end()
+    }
 }
 
 pub struct StateHandler<S, FT, FQ> {
@@ -273,137 +640,258 @@ where
     }
 }
 
+/// Settings of a [`Deserializer`], kept when the stream options are read.
+#[derive(Debug, Clone, Copy)]
+pub struct Settings {
+    // Accept terms the options do not allow
+    lenient: bool,
+    restart_on_options: bool,
+    cache_iris: bool,
+    // Frames handled so far, for error positions
+    frames: u64,
+}
 
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            lenient: false,
+            restart_on_options: false,
+            cache_iris: true,
+            frames: 0,
+        }
+    }
+}
 
+/// Decodes the frames of a stream, set up by the options row at its start.
 pub enum Deserializer<T: ToRdf> {
-    Inited(Inner<T>),
-    Empty,
+    Inited(Box<Inner<T>>),
+    Empty(Settings),
 }
 
 impl<T: ToRdf> Deserializer<T> {
     pub fn new() -> Self {
-        Deserializer::Empty
+        Deserializer::Empty(Settings::default())
+    }
+
+    fn settings(&mut self) -> &mut Settings {
+        match self {
+            Deserializer::Inited(inner) => &mut inner.settings,
+            Deserializer::Empty(settings) => settings,
+        }
+    }
+
+    /// Treats an options row as the start of a new stream that resets all lookup state, to
+    /// read concatenated Jelly files (disabled by default).
+    ///
+    /// A new stream starts with options that differ from the current ones, or with options
+    /// that are the first row of a frame after rows of other types. Other options rows
+    /// repeat the current options and keep the lookup state. Without restarts, repeated
+    /// options have to match the first ones.
+    pub fn set_stream_restart(&mut self, restart_on_options: bool) {
+        self.settings().restart_on_options = restart_on_options;
+    }
+
+    /// Accepts triple terms and generalized statements even when the stream options do
+    /// not enable `rdf_star` or `generalized_statements` (disabled by default).
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.settings().lenient = lenient;
+    }
+
+    /// Reuses the IRI term built from the same prefix and name entries before, instead of
+    /// building it from the lookup tables again (enabled by default). Only backends that
+    /// resolve IRIs through [`Inner::cached_iri`] use the cache.
+    pub fn set_iri_cache(&mut self, enabled: bool) {
+        self.settings().cache_iris = enabled;
+    }
+
+    /// Protocol version of the stream, `None` until the stream options are read.
+    pub fn version(&self) -> Option<u32> {
+        self.inner().map(Inner::version)
+    }
+
+    /// The decoding state, `None` until the stream options are read.
+    pub fn inner(&self) -> Option<&Inner<T>> {
+        match self {
+            Deserializer::Inited(inner) => Some(inner),
+            Deserializer::Empty(_) => None,
+        }
     }
-    pub fn handle_frame<H: RdfHandler<T>>(
+    pub fn handle_frame<H: TryRdfHandler<T>>(
         &mut self,
         frame: RdfStreamFrame,
         mut handler: H,
     ) -> Result<H, DeserializeError> {
-        let rows = frame.rows.into_iter().flat_map(|x| x.row);
-
-        for row in rows {
-            debug!("Row {:?}", row);
-            if let Row::Options(options) = &row {
-                match self {
-                    Deserializer::Inited(_) => {
-                        info!("Didn't expect new options, but I don't care, ignoring");
-                    }
-                    Deserializer::Empty => {
-                        *self = Deserializer::Inited(Inner::from_options(&options)?);
-                    }
-                }
+        let settings = self.settings();
+        let frame_index = settings.frames;
+        settings.frames += 1;
+        let position = |row, row_kind, location| ErrorPosition {
+            frame: frame_index,
+            row,
+            row_kind,
+            location,
+            offset: None,
+        };
+
+        handler
+            .try_handle_frame_start(&frame.metadata)
+            .map_err(|e| handler_error(e).at(position(None, None, None)))?;
+
+        let mut first_in_frame = true;
+        for (index, row) in frame.rows.into_iter().enumerate() {
+            let Some(row) = row.row else {
+                continue;
+            };
+            let row_kind = MessageType::of(&row);
+            if let Deserializer::Inited(inner) = self {
+                inner.location = None;
             }
+            let first = std::mem::take(&mut first_in_frame);
+            self.handle_row(row, first, &mut handler).map_err(|e| {
+                let location = self.inner().and_then(|inner| inner.location);
+                e.at(position(Some(index), Some(row_kind), location))
+            })?;
+        }
+
+        handler
+            .try_handle_frame_end()
+            .map_err(|e| handler_error(e).at(position(None, None, None)))?;
+        Ok(handler)
+    }
 
-            let thing = match self {
-                Deserializer::Inited(deserializer) => deserializer,
-                Deserializer::Empty => {
-                    return Err(DeserializeError::ConfigError(ConfigError::NotSet));
+    fn handle_row<H: TryRdfHandler<T>>(
+        &mut self,
+        row: Row,
+        first_in_frame: bool,
+        handler: &mut H,
+    ) -> Result<(), DeserializeError> {
+        debug!("Row {:?}", row);
+        if let Row::Options(options) = &row {
+            match self {
+                Deserializer::Inited(inner) if !inner.restarts_stream(options, first_in_frame) => {
+                    inner.check_repeated_options(options)?;
+                    inner.grow_tables(options);
                 }
-            };
+                _ => {
+                    let mut inner = Inner::from_options(options)?;
+                    inner.settings = *self.settings();
+                    *self = Deserializer::Inited(Box::new(inner));
+                }
+            }
+        }
 
-            match row {
-                Row::Options(_) => {}
-                Row::Triple(rdf_triple) => {
-                    if thing.physical_type == PhysicalStreamType::Graphs {
-                        if !thing.graph_started {
-                            return Err(DeserializeError::PhysicalStreamError(
-                                PhysicalStreamError::NotYetSet {
-                                    detected: thing.physical_type,
-                                    expected: MessageType::GraphStart,
-                                },
-                            ));
-                        } else {
-                            handler.handle_quad(thing.triple_with_graph(rdf_triple)?);
-                        }
-                    } else if thing.physical_type == PhysicalStreamType::Triples {
-                        handler.handle_triple(thing.triple(rdf_triple)?)
-                    } else {
+        let thing = match self {
+            Deserializer::Inited(deserializer) => deserializer,
+            Deserializer::Empty(_) => {
+                return Err(DeserializeError::ConfigError(ConfigError::NotSet));
+            }
+        };
+
+        if !matches!(row, Row::Options(_)) {
+            thing.other_rows = true;
+        }
+        match row {
+            Row::Options(_) => {}
+            Row::Triple(rdf_triple) => {
+                if thing.physical_type == PhysicalStreamType::Graphs {
+                    if !thing.graph_started {
                         return Err(DeserializeError::PhysicalStreamError(
-                            PhysicalStreamError::IncorrectType {
+                            PhysicalStreamError::NotYetSet {
                                 detected: thing.physical_type,
-                                incoming: MessageType::Triple,
+                                expected: MessageType::GraphStart,
                             },
                         ));
-                    }
-                }
-                Row::Quad(rdf_quad) => {
-                    println!("Quad");
-                    if thing.physical_type == PhysicalStreamType::Quads {
-                        handler.handle_quad(thing.quad(rdf_quad)?)
                     } else {
-                        return Err(DeserializeError::PhysicalStreamError(
-                            PhysicalStreamError::IncorrectType {
-                                detected: thing.physical_type,
-                                incoming: MessageType::Quad,
-                            },
-                        ));
+                        handler
+                            .try_handle_quad(thing.triple_with_graph(rdf_triple)?)
+                            .map_err(handler_error)?;
                     }
+                } else if thing.physical_type == PhysicalStreamType::Triples {
+                    handler
+                        .try_handle_triple(thing.triple(rdf_triple)?)
+                        .map_err(handler_error)?;
+                } else {
+                    return Err(DeserializeError::PhysicalStreamError(
+                        PhysicalStreamError::IncorrectType {
+                            detected: thing.physical_type,
+                            incoming: MessageType::Triple,
+                        },
+                    ));
                 }
-                Row::GraphStart(rdf_graph_start) => {
-                    println!("Graph start! {:?}", rdf_graph_start.graph);
-
-                    if thing.physical_type == PhysicalStreamType::Graphs {
-                        let g = match rdf_graph_start.graph {
-                            Some(gs::Graph::GIri(iri)) => Graph::GIri(iri),
-                            Some(gs::Graph::GDefaultGraph(iri)) => Graph::GDefaultGraph(iri),
-                            Some(gs::Graph::GBnode(iri)) => Graph::GBnode(iri),
-                            Some(gs::Graph::GLiteral(iri)) => Graph::GLiteral(iri),
-                            None => {
-                                return Result::Err(DeserializeError::MissingTerm(
-                                    TermLocation::Graph,
-                                ));
-                            }
-                        };
-                        thing.q_graph(g)?;
-                        thing.graph_started = true;
-                    } else {
-                        return Err(DeserializeError::PhysicalStreamError(
-                            PhysicalStreamError::IncorrectType {
-                                detected: thing.physical_type,
-                                incoming: MessageType::GraphStart,
-                            },
-                        ));
-                    }
+            }
+            Row::Quad(rdf_quad) => {
+                if thing.physical_type == PhysicalStreamType::Quads {
+                    handler
+                        .try_handle_quad(thing.quad(rdf_quad)?)
+                        .map_err(handler_error)?;
+                } else {
+                    return Err(DeserializeError::PhysicalStreamError(
+                        PhysicalStreamError::IncorrectType {
+                            detected: thing.physical_type,
+                            incoming: MessageType::Quad,
+                        },
+                    ));
                 }
-                Row::GraphEnd(_) => {
-                    if thing.physical_type == PhysicalStreamType::Graphs {
-                        println!("Graph end!");
-                        thing.last_graph = None;
-                        thing.graph_started = false;
-                    } else {
-                        return Err(DeserializeError::PhysicalStreamError(
-                            PhysicalStreamError::IncorrectType {
-                                detected: thing.physical_type,
-                                incoming: MessageType::GraphEnd,
-                            },
-                        ));
-                    }
+            }
+            Row::GraphStart(rdf_graph_start) => {
+                if thing.physical_type == PhysicalStreamType::Graphs {
+                    let g = match rdf_graph_start.graph {
+                        Some(gs::Graph::GIri(iri)) => Graph::GIri(iri),
+                        Some(gs::Graph::GDefaultGraph(iri)) => Graph::GDefaultGraph(iri),
+                        Some(gs::Graph::GBnode(iri)) => Graph::GBnode(iri),
+                        Some(gs::Graph::GLiteral(iri)) => Graph::GLiteral(iri),
+                        None => {
+                            return Result::Err(DeserializeError::MissingTerm(TermLocation::Graph));
+                        }
+                    };
+                    thing.location = Some(TermLocation::Graph);
+                    thing.q_graph(g)?;
+                    thing.graph_started = true;
+                    handler
+                        .try_handle_graph_start(thing.last_graph.as_ref())
+                        .map_err(handler_error)?;
+                } else {
+                    return Err(DeserializeError::PhysicalStreamError(
+                        PhysicalStreamError::IncorrectType {
+                            detected: thing.physical_type,
+                            incoming: MessageType::GraphStart,
+                        },
+                    ));
                 }
-                Row::Namespace(rdf_namespace_declaration) => {
-                    info!("Name space is fine: {} ", rdf_namespace_declaration.name,);
+            }
+            Row::GraphEnd(_) => {
+                if thing.physical_type == PhysicalStreamType::Graphs {
+                    thing.last_graph = None;
+                    thing.graph_started = false;
+                    handler.try_handle_graph_end().map_err(handler_error)?;
+                } else {
+                    return Err(DeserializeError::PhysicalStreamError(
+                        PhysicalStreamError::IncorrectType {
+                            detected: thing.physical_type,
+                            incoming: MessageType::GraphEnd,
+                        },
+                    ));
                 }
-                Row::Name(rdf_name_entry) => thing.name_entry(rdf_name_entry)?,
-                Row::Prefix(rdf_prefix_entry) => thing.prefix_entry(rdf_prefix_entry)?,
-                Row::Datatype(rdf_datatype_entry) => thing.datatype_entry(rdf_datatype_entry)?,
             }
+            Row::Namespace(rdf_namespace_declaration) => {
+                let iri = rdf_namespace_declaration
+                    .value
+                    .ok_or(DeserializeError::MissingTerm(TermLocation::Namespace))?;
+                let iri = thing.namespace_iri(iri)?;
+                handler
+                    .try_handle_namespace(&rdf_namespace_declaration.name, &iri)
+                    .map_err(handler_error)?;
+            }
+            Row::Name(rdf_name_entry) => thing.name_entry(rdf_name_entry)?,
+            Row::Prefix(rdf_prefix_entry) => thing.prefix_entry(rdf_prefix_entry)?,
+            Row::Datatype(rdf_datatype_entry) => thing.datatype_entry(rdf_datatype_entry)?,
         }
-        Ok(handler)
+        Ok(())
     }
 }
 
 impl<T: ToRdf> Default for Deserializer<T> {
     fn default() -> Self {
-        Deserializer::Empty
+        Self::new()
     }
 }
 
@@ -415,7 +903,7 @@ where T: ToOwned,
     fn clone(&self) -> Self {
         match self {
             Deserializer::Inited(inner) => Deserializer::Inited(inner.to_owned()),
-            Deserializer::Empty => Deserializer::Empty,
+            Deserializer::Empty(settings) => Deserializer::Empty(*settings),
         }
     }
 }
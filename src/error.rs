@@ -1,7 +1,10 @@
 use prost::UnknownEnumValue;
 use thiserror::Error;
 
-use crate::proto::PhysicalStreamType;
+use crate::proto::rdf_stream_row::Row;
+use crate::proto::{LogicalStreamType, PROTO_VERSION, PhysicalStreamType};
+#[cfg(feature = "value")]
+use crate::value::XsdType;
 
 #[derive(Error, Debug)]
 pub enum DeserializeError {
@@ -15,6 +18,29 @@ pub enum DeserializeError {
     MissingTerm(TermLocation),
     #[error("lookup error {0}")]
     LookupError(#[from] LookupError),
+    #[error("triple term in {0:?}, but the stream does not enable rdf_star")]
+    RdfStarNotEnabled(TermLocation),
+    #[error("{kind:?} in {location:?}, but the stream does not enable generalized_statements")]
+    GeneralizedNotEnabled {
+        location: TermLocation,
+        kind: TermKind,
+    },
+    #[error("{kind:?} in {location:?} is not supported by the term backend")]
+    UnsupportedTerm {
+        location: TermLocation,
+        kind: TermKind,
+    },
+    #[error("frame of a {0:?} stream holds more than one named graph")]
+    MultipleNamedGraphs(LogicalStreamType),
+    #[error("handler error: {0}")]
+    Handler(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("{0}")]
+    FrameRead(#[from] FrameReadError),
+    #[error("{source} at {position}")]
+    Positioned {
+        position: ErrorPosition,
+        source: Box<DeserializeError>,
+    },
 
     // Sophia errors
     #[cfg(feature = "sophia")]
@@ -23,14 +49,115 @@ pub enum DeserializeError {
     #[cfg(feature = "sophia")]
     #[error("Missing term in term triple {0}")]
     InvalidIri(#[from] sophia_iri::InvalidIri),
+
+    // oxrdf errors
+    #[cfg(feature = "oxrdf")]
+    #[error("invalid language tag: {0}")]
+    OxInvalidLanguageTag(#[from] oxrdf::LanguageTagParseError),
+    #[cfg(feature = "oxrdf")]
+    #[error("invalid IRI: {0}")]
+    OxInvalidIri(#[from] oxrdf::IriParseError),
 }
 
-#[derive(Debug)]
+impl DeserializeError {
+    /// Where in the stream the error happened, if known.
+    pub fn position(&self) -> Option<&ErrorPosition> {
+        match self {
+            DeserializeError::Positioned { position, .. } => Some(position),
+            _ => None,
+        }
+    }
+
+    /// The error without its position.
+    pub fn kind(&self) -> &DeserializeError {
+        match self {
+            DeserializeError::Positioned { source, .. } => source.kind(),
+            error => error,
+        }
+    }
+
+    pub(crate) fn at(self, position: ErrorPosition) -> Self {
+        match self {
+            // Keep the position closest to where the error happened
+            DeserializeError::Positioned { .. } => self,
+            error => DeserializeError::Positioned {
+                position,
+                source: Box::new(error),
+            },
+        }
+    }
+
+    /// Adds the byte offset of the frame the error happened in.
+    pub(crate) fn with_offset(mut self, offset: u64) -> Self {
+        if let DeserializeError::Positioned { position, .. } = &mut self {
+            position.offset = Some(offset);
+        }
+        self
+    }
+}
+
+/// Where in the stream a [`DeserializeError`] happened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorPosition {
+    /// Index of the frame, counting all frames given to the deserializer
+    pub frame: u64,
+    /// Index of the row in the frame, `None` for errors outside of the rows
+    pub row: Option<usize>,
+    pub row_kind: Option<MessageType>,
+    /// The term being decoded
+    pub location: Option<TermLocation>,
+    /// Byte offset of the frame, known when it was read with a [`crate::FrameReader`]
+    pub offset: Option<u64>,
+}
+
+impl std::fmt::Display for ErrorPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "frame {}", self.frame)?;
+        if let Some(offset) = self.offset {
+            write!(f, " (byte {})", offset)?;
+        }
+        if let Some(row) = self.row {
+            write!(f, ", row {}", row)?;
+        }
+        if let Some(row_kind) = self.row_kind {
+            write!(f, " ({:?})", row_kind)?;
+        }
+        if let Some(location) = self.location {
+            write!(f, ", {:?}", location)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TermLocation {
     Subject,
     Predicate,
     Object,
     Graph,
+    Namespace,
+}
+
+impl TermLocation {
+    /// Whether plain RDF allows a term of `kind` here, other kinds need generalized
+    /// statements. Triple terms additionally need RDF-star.
+    pub fn allows(&self, kind: TermKind) -> bool {
+        match self {
+            TermLocation::Subject => kind != TermKind::Literal,
+            TermLocation::Predicate => kind == TermKind::Iri,
+            TermLocation::Object => true,
+            TermLocation::Graph => matches!(kind, TermKind::Iri | TermKind::BlankNode),
+            TermLocation::Namespace => kind == TermKind::Iri,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermKind {
+    Iri,
+    BlankNode,
+    Literal,
+    TripleTerm,
 }
 
 #[derive(Error, Debug)]
@@ -57,10 +184,66 @@ pub enum ConfigError {
     InvalidPhysicalNotSet(#[from] UnknownEnumValue),
     #[error("Table {table:?} to large ({set} > {max})")]
     TableToLarge { table: Table, set: u32, max: u32 },
+    #[error("Table {table:?} too small ({set} < {min})")]
+    TableTooSmall { table: Table, set: u32, min: u32 },
+    #[error("Logical Stream Type {0} is not supported")]
+    InvalidLogicalType(i32),
+    #[error(
+        "Logical Stream Type {logical:?} can not be used with Physical Stream Type {physical:?}"
+    )]
+    IncompatibleLogicalType {
+        logical: LogicalStreamType,
+        physical: PhysicalStreamType,
+    },
+    #[error("Protocol version {version} is not supported, only versions up to {supported}")]
+    UnsupportedVersion { version: u32, supported: u32 },
+    #[error("Stream options changed {0} mid-stream")]
+    OptionsChanged(&'static str),
     #[error("No config set")]
     NotSet,
 }
 impl ConfigError {
+    /// Streams of newer versions than this crate implements are rejected, streams with
+    /// an unset (0) version are read like 1.0.x streams.
+    pub fn version(version: u32) -> Option<Self> {
+        (version > PROTO_VERSION).then_some(ConfigError::UnsupportedVersion {
+            version,
+            supported: PROTO_VERSION,
+        })
+    }
+
+    /// Parses the logical stream type and checks that the physical type can carry it.
+    ///
+    /// Subtypes like `SubjectGraphs` share the physical types of their base type, the last
+    /// digit of their value.
+    pub fn logical_type(
+        logical: i32,
+        physical: PhysicalStreamType,
+    ) -> Result<LogicalStreamType, Self> {
+        let logical_type = LogicalStreamType::try_from(logical)
+            .map_err(|_| ConfigError::InvalidLogicalType(logical))?;
+        let compatible = match logical_type {
+            LogicalStreamType::Unspecified => true,
+            LogicalStreamType::FlatTriples
+            | LogicalStreamType::Graphs
+            | LogicalStreamType::SubjectGraphs => physical == PhysicalStreamType::Triples,
+            LogicalStreamType::FlatQuads
+            | LogicalStreamType::Datasets
+            | LogicalStreamType::NamedGraphs
+            | LogicalStreamType::TimestampedNamedGraphs => matches!(
+                physical,
+                PhysicalStreamType::Quads | PhysicalStreamType::Graphs
+            ),
+        };
+        if !compatible {
+            return Err(ConfigError::IncompatibleLogicalType {
+                logical: logical_type,
+                physical,
+            });
+        }
+        Ok(logical_type)
+    }
+
     pub fn name_table(set: u32) -> Option<Self> {
         let max = 4096;
         (set > max).then_some(ConfigError::TableToLarge {
@@ -89,6 +272,82 @@ impl ConfigError {
     }
 }
 
+#[derive(Error, Debug)]
+pub enum FrameReadError {
+    #[error("io error at byte {offset}: {source}")]
+    Io {
+        offset: u64,
+        source: std::io::Error,
+    },
+    #[error("stream ends inside the length prefix starting at byte {offset}")]
+    TruncatedLength { offset: u64 },
+    #[error("length prefix at byte {offset} is longer than 10 bytes")]
+    InvalidLength { offset: u64 },
+    #[error("frame at byte {offset} is {len} bytes, more than the maximum of {max}")]
+    FrameTooLarge { offset: u64, len: u64, max: u64 },
+    #[error("frame at byte {offset} exceeds the stream limit of {max} bytes")]
+    StreamTooLarge { offset: u64, max: u64 },
+    #[error("frame at byte {offset} has at least {rows} rows, more than the maximum of {max}")]
+    TooManyRows {
+        offset: u64,
+        rows: usize,
+        max: usize,
+    },
+    #[error("frame at byte {offset} has an entry of {len} bytes, more than the maximum of {max}")]
+    StringTooLong {
+        offset: u64,
+        len: usize,
+        max: usize,
+    },
+    #[error("stream ends inside the frame at byte {offset}, read {read} of {expected} bytes")]
+    TruncatedFrame { offset: u64, expected: u64, read: u64 },
+    #[error("could not decode frame at byte {offset}: {source}")]
+    Decode {
+        offset: u64,
+        source: prost::DecodeError,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum MetadataError {
+    #[error("metadata entry {key:?} is not valid UTF-8")]
+    InvalidUtf8 { key: String },
+    #[error("metadata entry {key:?} has {len} bytes, expected {expected}")]
+    InvalidLength {
+        key: String,
+        len: usize,
+        expected: usize,
+    },
+}
+
+#[cfg(feature = "value")]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ValueError {
+    #[error("{lex:?} is not a valid {datatype}")]
+    Malformed { datatype: XsdType, lex: String },
+}
+
+#[derive(Error, Debug)]
+pub enum SerializeError {
+    #[error("{0}")]
+    ConfigError(#[from] ConfigError),
+    #[error("{0}")]
+    PhysicalStreamError(#[from] PhysicalStreamError),
+    #[error("Term can not be represented in a Jelly stream")]
+    UnsupportedTerm,
+    #[error("lookup error {0}")]
+    LookupError(#[from] LookupError),
+    #[error("triple term in {0:?}, but the stream does not enable rdf_star")]
+    RdfStarNotEnabled(TermLocation),
+    #[error("{kind:?} in {location:?}, but the stream does not enable generalized_statements")]
+    GeneralizedNotEnabled {
+        location: TermLocation,
+        kind: TermKind,
+    },
+    #[error("Table {0:?} is disabled but required for this term")]
+    TableDisabled(Table),
+}
+
 #[derive(Debug)]
 pub enum Table {
     NameTable,
@@ -96,12 +355,33 @@ pub enum Table {
     PrefixTable,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MessageType {
     Quad,
     Triple,
     GraphStart,
     GraphEnd,
+    Options,
+    Namespace,
+    Name,
+    Prefix,
+    Datatype,
+}
+
+impl MessageType {
+    pub fn of(row: &Row) -> Self {
+        match row {
+            Row::Options(_) => MessageType::Options,
+            Row::Triple(_) => MessageType::Triple,
+            Row::Quad(_) => MessageType::Quad,
+            Row::GraphStart(_) => MessageType::GraphStart,
+            Row::GraphEnd(_) => MessageType::GraphEnd,
+            Row::Namespace(_) => MessageType::Namespace,
+            Row::Name(_) => MessageType::Name,
+            Row::Prefix(_) => MessageType::Prefix,
+            Row::Datatype(_) => MessageType::Datatype,
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -0,0 +1,78 @@
+#[cfg(feature = "sophia")]
+mod sophia;
+
+/// An RDF term as understood by the [`Serializer`](crate::serialize::Serializer).
+///
+/// The string type is generic so the same shape can be used borrowed ([`TermRef`]) while
+/// encoding and owned ([`Term<String>`]) when a term has to be kept around.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Term<S = String> {
+    Iri(S),
+    BlankNode(S),
+    Literal(S, LiteralKind<S>),
+    Triple(Box<[Term<S>; 3]>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LiteralKind<S = String> {
+    Simple,
+    Language(S),
+    Datatype(S),
+}
+
+pub type TermRef<'a> = Term<&'a str>;
+
+impl<S: AsRef<str>> Term<S> {
+    pub fn borrowed(&self) -> TermRef<'_> {
+        match self {
+            Term::Iri(iri) => Term::Iri(iri.as_ref()),
+            Term::BlankNode(id) => Term::BlankNode(id.as_ref()),
+            Term::Literal(lex, kind) => Term::Literal(
+                lex.as_ref(),
+                match kind {
+                    LiteralKind::Simple => LiteralKind::Simple,
+                    LiteralKind::Language(tag) => LiteralKind::Language(tag.as_ref()),
+                    LiteralKind::Datatype(dt) => LiteralKind::Datatype(dt.as_ref()),
+                },
+            ),
+            Term::Triple(triple) => Term::Triple(Box::new([
+                triple[0].borrowed(),
+                triple[1].borrowed(),
+                triple[2].borrowed(),
+            ])),
+        }
+    }
+
+    pub fn to_owned_term(&self) -> Term<String> {
+        match self {
+            Term::Iri(iri) => Term::Iri(iri.as_ref().to_string()),
+            Term::BlankNode(id) => Term::BlankNode(id.as_ref().to_string()),
+            Term::Literal(lex, kind) => Term::Literal(
+                lex.as_ref().to_string(),
+                match kind {
+                    LiteralKind::Simple => LiteralKind::Simple,
+                    LiteralKind::Language(tag) => LiteralKind::Language(tag.as_ref().to_string()),
+                    LiteralKind::Datatype(dt) => LiteralKind::Datatype(dt.as_ref().to_string()),
+                },
+            ),
+            Term::Triple(triple) => Term::Triple(Box::new([
+                triple[0].to_owned_term(),
+                triple[1].to_owned_term(),
+                triple[2].to_owned_term(),
+            ])),
+        }
+    }
+}
+
+/// Terms of an RDF library that can be written to a Jelly stream.
+///
+/// Returns `None` for terms that have no Jelly representation (e.g. variables).
+pub trait AsTermRef {
+    fn as_term_ref(&self) -> Option<TermRef<'_>>;
+}
+
+impl<S: AsRef<str>> AsTermRef for Term<S> {
+    fn as_term_ref(&self) -> Option<TermRef<'_>> {
+        Some(self.borrowed())
+    }
+}
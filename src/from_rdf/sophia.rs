@@ -0,0 +1,29 @@
+use sophia_term::{ArcTerm, GenericLiteral};
+
+use super::{AsTermRef, LiteralKind, Term, TermRef};
+
+const RDF_LANG_STRING: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#langString";
+
+impl AsTermRef for ArcTerm {
+    fn as_term_ref(&self) -> Option<TermRef<'_>> {
+        Some(match self {
+            ArcTerm::Iri(iri) => Term::Iri(iri.as_str()),
+            ArcTerm::BlankNode(id) => Term::BlankNode(id.as_str()),
+            ArcTerm::Literal(GenericLiteral::Typed(lex, datatype)) => {
+                if datatype.as_str() == RDF_LANG_STRING {
+                    return None;
+                }
+                Term::Literal(&**lex, LiteralKind::Datatype(datatype.as_str()))
+            }
+            ArcTerm::Literal(GenericLiteral::LanguageString(lex, tag, _)) => {
+                Term::Literal(&**lex, LiteralKind::Language(tag.as_str()))
+            }
+            ArcTerm::Triple(triple) => Term::Triple(Box::new([
+                triple[0].as_term_ref()?,
+                triple[1].as_term_ref()?,
+                triple[2].as_term_ref()?,
+            ])),
+            ArcTerm::Variable(_) => return None,
+        })
+    }
+}
@@ -0,0 +1,502 @@
+use std::io::{BufWriter, Chain, Cursor, Read, Write};
+
+use crate::error::FrameReadError;
+use crate::proto::RdfStreamFrame;
+use prost::Message as _;
+
+/// Protobuf messages can not be larger than 2 GiB
+const MAX_FRAME_LEN: u64 = i32::MAX as u64;
+
+/// Limits a [`FrameReader`] enforces on its input, so untrusted streams can not make it
+/// allocate or decode arbitrary amounts of data.
+///
+/// The defaults only reject what can not be a valid stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReaderLimits {
+    /// Maximum length of a single frame in bytes
+    pub max_frame_bytes: u64,
+    /// Maximum number of rows in a single frame
+    pub max_rows_per_frame: usize,
+    /// Maximum number of bytes read from the stream in total
+    pub max_stream_bytes: u64,
+    /// Maximum length of the value of a name, prefix or datatype entry
+    pub max_string_len: usize,
+}
+
+impl Default for ReaderLimits {
+    fn default() -> Self {
+        Self {
+            max_frame_bytes: MAX_FRAME_LEN,
+            max_rows_per_frame: usize::MAX,
+            max_stream_bytes: u64::MAX,
+            max_string_len: usize::MAX,
+        }
+    }
+}
+
+impl ReaderLimits {
+    /// Checks the length prefix of the frame at `start`, `offset` is just after the prefix
+    pub(crate) fn check_len(
+        &self,
+        len: u64,
+        start: u64,
+        offset: u64,
+    ) -> Result<(), FrameReadError> {
+        let max = self.max_frame_bytes.min(MAX_FRAME_LEN);
+        if len > max {
+            return Err(FrameReadError::FrameTooLarge {
+                offset: start,
+                len,
+                max,
+            });
+        }
+        if offset.saturating_add(len) > self.max_stream_bytes {
+            return Err(FrameReadError::StreamTooLarge {
+                offset: start,
+                max: self.max_stream_bytes,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the frame at `start` from its bytes and decodes it
+    pub(crate) fn decode_frame(
+        &self,
+        buf: &[u8],
+        start: u64,
+    ) -> Result<RdfStreamFrame, FrameReadError> {
+        self.check_frame(buf, start)?;
+        RdfStreamFrame::decode(buf).map_err(|source| FrameReadError::Decode {
+            offset: start,
+            source,
+        })
+    }
+
+    /// Walks the encoded frame and stops at the first row or entry over the limits, so
+    /// nothing of it is decoded. Malformed input ends the walk, decoding reports it.
+    fn check_frame(&self, mut buf: &[u8], offset: u64) -> Result<(), FrameReadError> {
+        if self.max_rows_per_frame == usize::MAX && self.max_string_len == usize::MAX {
+            return Ok(());
+        }
+
+        let mut rows = 0;
+        while let Some((tag, value)) = next_field(&mut buf) {
+            // RdfStreamFrame.rows
+            let (1, Some(row)) = (tag, value) else {
+                continue;
+            };
+            rows += 1;
+            if rows > self.max_rows_per_frame {
+                return Err(FrameReadError::TooManyRows {
+                    offset,
+                    rows,
+                    max: self.max_rows_per_frame,
+                });
+            }
+            if self.max_string_len != usize::MAX {
+                self.check_row(row, offset)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_row(&self, mut row: &[u8], offset: u64) -> Result<(), FrameReadError> {
+        while let Some((tag, value)) = next_field(&mut row) {
+            // RdfStreamRow.name, prefix and datatype
+            let (9..=11, Some(mut entry)) = (tag, value) else {
+                continue;
+            };
+            while let Some((tag, value)) = next_field(&mut entry) {
+                // The value of the entry
+                let (2, Some(value)) = (tag, value) else {
+                    continue;
+                };
+                if value.len() > self.max_string_len {
+                    return Err(FrameReadError::StringTooLong {
+                        offset,
+                        len: value.len(),
+                        max: self.max_string_len,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads a varint from the front of `buf`, `None` if it is malformed or truncated
+fn take_varint(buf: &mut &[u8]) -> Option<u64> {
+    let mut varint = VarintDecoder::default();
+    while let Some((&byte, rest)) = buf.split_first() {
+        *buf = rest;
+        if let Some(value) = varint.push(byte, 0).ok()? {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn take_bytes<'a>(buf: &mut &'a [u8], len: u64) -> Option<&'a [u8]> {
+    let len = usize::try_from(len).ok().filter(|len| *len <= buf.len())?;
+    let (bytes, rest) = buf.split_at(len);
+    *buf = rest;
+    Some(bytes)
+}
+
+/// Reads the value of a field with `wire_type` from the front of `buf`, with its bytes if
+/// it is length delimited
+fn take_value<'a>(buf: &mut &'a [u8], wire_type: u64) -> Option<Option<&'a [u8]>> {
+    match wire_type {
+        0 => take_varint(buf).map(|_| None),
+        1 => take_bytes(buf, 8).map(|_| None),
+        2 => {
+            let len = take_varint(buf)?;
+            take_bytes(buf, len).map(Some)
+        }
+        5 => take_bytes(buf, 4).map(|_| None),
+        _ => None,
+    }
+}
+
+/// Reads the next field from the front of an encoded message, with its bytes if it is
+/// length delimited. `None` at the end of the message or if it is malformed.
+fn next_field<'a>(buf: &mut &'a [u8]) -> Option<(u64, Option<&'a [u8]>)> {
+    if buf.is_empty() {
+        return None;
+    }
+    let key = take_varint(buf)?;
+    let tag = key >> 3;
+    if key & 0x07 != 3 {
+        return take_value(buf, key & 0x07).map(|value| (tag, value));
+    }
+
+    // Groups are skipped up to their matching end
+    let mut depth = 1usize;
+    while depth > 0 {
+        match take_varint(buf)? & 0x07 {
+            3 => depth += 1,
+            4 => depth -= 1,
+            wire_type => {
+                take_value(buf, wire_type)?;
+            }
+        }
+    }
+    Some((tag, None))
+}
+
+/// Incremental Protobuf varint decoder, fed one byte at a time so blocking and async
+/// readers share it.
+#[derive(Debug, Default)]
+pub(crate) struct VarintDecoder {
+    value: u64,
+    shift: u32,
+}
+
+impl VarintDecoder {
+    /// Returns the value once its last byte was pushed, `start` is the offset of the
+    /// first byte used in errors.
+    pub(crate) fn push(&mut self, byte: u8, start: u64) -> Result<Option<u64>, FrameReadError> {
+        self.value |= ((byte & 0x7F) as u64) << self.shift;
+        self.shift += 7;
+
+        if byte & 0x80 == 0 {
+            let value = self.value;
+            *self = Self::default();
+            return Ok(Some(value));
+        }
+        // A varint has at most 10 bytes
+        if self.shift >= 70 {
+            return Err(FrameReadError::InvalidLength { offset: start });
+        }
+        Ok(None)
+    }
+}
+
+/// Read a Protobuf varint from an std::io::Read, `None` on EOF before its first byte
+fn read_varint<R: Read>(reader: &mut R, offset: &mut u64) -> Result<Option<u64>, FrameReadError> {
+    let start = *offset;
+    let mut varint = VarintDecoder::default();
+
+    loop {
+        let mut byte = [0u8];
+        let read = loop {
+            match reader.read(&mut byte) {
+                Ok(read) => break read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(source) => {
+                    return Err(FrameReadError::Io {
+                        offset: *offset,
+                        source,
+                    });
+                }
+            }
+        };
+        if read == 0 {
+            if *offset == start {
+                return Ok(None);
+            }
+            return Err(FrameReadError::TruncatedLength { offset: start });
+        }
+        *offset += 1;
+
+        if let Some(value) = varint.push(byte[0], start)? {
+            return Ok(Some(value));
+        }
+    }
+}
+
+/// Detects whether a stream starting with `scout` (its first 3 bytes, or less for shorter
+/// streams) is delimited, as described in the Jelly specification.
+///
+/// A non-delimited frame starts with the rows field (`0x0A`), followed by the length of
+/// the first row and the options field of that row (`0x0A` again). A delimited stream
+/// starts with the frame length instead. Like the reference implementation, input
+/// shorter than 3 bytes is taken as non-delimited.
+pub fn is_delimited(scout: &[u8]) -> bool {
+    match scout {
+        // NN ?? ??: delimited, every non-delimited frame starts with 0A
+        [first, _, _, ..] if *first != 0x0A => true,
+        // 0A 0A NN: delimited frame of 10 bytes
+        // 0A 0A 0A: non-delimited, stream options of 10 bytes
+        [0x0A, 0x0A, third, ..] => *third != 0x0A,
+        // 0A NN ??: non-delimited, all delimited frames of length 10 start with 0A 0A
+        _ => false,
+    }
+}
+
+/// Reads length delimited frames, or a single non-delimited frame.
+///
+/// Yields an error when the stream is corrupted or ends inside a frame, after which the
+/// iterator ends. A stream that ends at a frame boundary just ends the iterator.
+pub struct FrameReader<R> {
+    reader: R,
+    limits: ReaderLimits,
+    delimited: bool,
+    offset: u64,
+    buf: Vec<u8>,
+    done: bool,
+}
+impl<R> FrameReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            limits: ReaderLimits::default(),
+            delimited: true,
+            offset: 0,
+            buf: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Reads the whole input as a single frame without length prefix.
+    pub fn non_delimited(reader: R) -> Self {
+        Self {
+            delimited: false,
+            ..Self::new(reader)
+        }
+    }
+
+    pub fn is_delimited(&self) -> bool {
+        self.delimited
+    }
+
+    pub fn set_limits(&mut self, limits: ReaderLimits) {
+        self.limits = limits;
+    }
+
+    /// Number of bytes read so far.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl<R: Read> FrameReader<R> {
+    /// Looks at the first bytes of the input to detect whether it is delimited or not.
+    ///
+    /// The bytes used for detection are chained back in front of the reader.
+    pub fn auto(mut reader: R) -> std::io::Result<FrameReader<Chain<Cursor<Vec<u8>>, R>>> {
+        let mut scout = Vec::with_capacity(3);
+        (&mut reader).take(3).read_to_end(&mut scout)?;
+
+        let delimited = is_delimited(&scout);
+        let reader = Cursor::new(scout).chain(reader);
+        Ok(if delimited {
+            FrameReader::new(reader)
+        } else {
+            FrameReader::non_delimited(reader)
+        })
+    }
+
+    fn read_frame(&mut self) -> Result<Option<RdfStreamFrame>, FrameReadError> {
+        if !self.delimited {
+            return self.read_non_delimited();
+        }
+        let start = self.offset;
+
+        // Decode a varint (length prefix)
+        let Some(len) = read_varint(&mut self.reader, &mut self.offset)? else {
+            return Ok(None);
+        };
+        self.limits.check_len(len, start, self.offset)?;
+
+        // The buffer grows with the bytes actually read instead of trusting the prefix
+        self.buf.clear();
+        let read = (&mut self.reader)
+            .take(len)
+            .read_to_end(&mut self.buf)
+            .map_err(|source| FrameReadError::Io {
+                offset: self.offset + self.buf.len() as u64,
+                source,
+            })? as u64;
+        self.offset += read;
+        if read < len {
+            return Err(FrameReadError::TruncatedFrame {
+                offset: start,
+                expected: len,
+                read,
+            });
+        }
+
+        // Decode the message from the buffer
+        self.limits.decode_frame(&self.buf, start).map(Some)
+    }
+
+    fn read_non_delimited(&mut self) -> Result<Option<RdfStreamFrame>, FrameReadError> {
+        self.done = true;
+
+        let max = self.limits.max_frame_bytes.min(MAX_FRAME_LEN);
+        let max_stream = self.limits.max_stream_bytes.saturating_sub(self.offset);
+
+        self.buf.clear();
+        let read = (&mut self.reader)
+            .take(max.min(max_stream) + 1)
+            .read_to_end(&mut self.buf)
+            .map_err(|source| FrameReadError::Io {
+                offset: self.buf.len() as u64,
+                source,
+            })? as u64;
+        self.offset += read;
+        if read == 0 {
+            return Ok(None);
+        }
+        if read > max_stream {
+            return Err(FrameReadError::StreamTooLarge {
+                offset: 0,
+                max: self.limits.max_stream_bytes,
+            });
+        }
+        if read > max {
+            // Only known to be larger than the limit, the rest of the input is not read
+            return Err(FrameReadError::FrameTooLarge {
+                offset: 0,
+                len: read,
+                max,
+            });
+        }
+
+        self.limits.decode_frame(&self.buf, 0).map(Some)
+    }
+}
+
+impl<R: Read> Iterator for FrameReader<R> {
+    type Item = Result<RdfStreamFrame, FrameReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let frame = self.read_frame().transpose();
+        self.done |= !matches!(frame, Some(Ok(_)));
+        frame
+    }
+}
+
+/// Writes length delimited frames, the counterpart of [`FrameReader`], or a single
+/// non-delimited frame.
+///
+/// Writes are buffered, [`FrameWriter::flush`] or dropping the writer flushes them.
+/// When writing to a pipe or socket, [`FrameWriter::set_flush_after_frame`] makes each
+/// frame available to the reader as soon as it is written.
+pub struct FrameWriter<W: Write> {
+    writer: BufWriter<W>,
+    buf: Vec<u8>,
+    flush_after_frame: bool,
+    delimited: bool,
+    written: bool,
+}
+
+impl<W: Write> FrameWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_capacity(8 * 1024, writer)
+    }
+
+    pub fn with_capacity(capacity: usize, writer: W) -> Self {
+        Self {
+            writer: BufWriter::with_capacity(capacity, writer),
+            buf: Vec::new(),
+            flush_after_frame: false,
+            delimited: true,
+            written: false,
+        }
+    }
+
+    /// Writes a single frame without length prefix, writing a second frame fails.
+    pub fn non_delimited(writer: W) -> Self {
+        Self {
+            delimited: false,
+            ..Self::new(writer)
+        }
+    }
+
+    pub fn set_flush_after_frame(&mut self, flush_after_frame: bool) {
+        self.flush_after_frame = flush_after_frame;
+    }
+
+    /// Writes the frame, prefixed with its length as varint in a delimited stream.
+    pub fn write_frame(&mut self, frame: &RdfStreamFrame) -> std::io::Result<()> {
+        if !self.delimited && self.written {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "a non-delimited stream holds a single frame",
+            ));
+        }
+
+        self.buf.clear();
+        let encoded = if self.delimited {
+            frame.encode_length_delimited(&mut self.buf)
+        } else {
+            frame.encode(&mut self.buf)
+        };
+        encoded.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        self.writer.write_all(&self.buf)?;
+        self.written = true;
+
+        if self.flush_after_frame {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn write_frames<I>(&mut self, frames: I) -> std::io::Result<()>
+    where
+        I: IntoIterator<Item = RdfStreamFrame>,
+    {
+        for frame in frames {
+            self.write_frame(&frame)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.writer.get_ref()
+    }
+
+    /// Flushes the buffer and returns the underlying writer.
+    pub fn into_inner(self) -> std::io::Result<W> {
+        self.writer.into_inner().map_err(|e| e.into_error())
+    }
+}
@@ -0,0 +1,120 @@
+use crate::deserialize::{Deserializer, RdfHandler};
+use crate::error::{ConfigError, DeserializeError};
+use crate::proto::{LogicalStreamType, PhysicalStreamType, RdfStreamFrame};
+use crate::to_rdf::{ToOwnedRdf, ToRdf};
+
+/// A triple with its graph, `None` is the default graph.
+pub type OwnedQuad<T> = (<T as ToOwnedRdf>::OwnedTriple, Option<<T as ToRdf>::Term>);
+
+/// The statements of one frame, grouped as the logical stream type describes them.
+pub enum LogicalFrame<T: ToOwnedRdf> {
+    /// Part of the single graph of a `FlatTriples` stream
+    Triples(Vec<T::OwnedTriple>),
+    /// Part of the single dataset of a `FlatQuads` stream
+    Quads(Vec<OwnedQuad<T>>),
+    /// A whole graph of a `Graphs` or `SubjectGraphs` stream
+    Graph(Vec<T::OwnedTriple>),
+    /// A whole dataset of a `Datasets` stream
+    Dataset(Vec<OwnedQuad<T>>),
+    /// A named graph of a `NamedGraphs` or `TimestampedNamedGraphs` stream, `name` is
+    /// `None` when the frame only holds the default graph.
+    ///
+    /// Default graph statements next to the named graph, like the timestamp of a
+    /// `TimestampedNamedGraphs` frame, are kept in `default_graph`.
+    NamedGraph {
+        name: Option<T::Term>,
+        triples: Vec<T::OwnedTriple>,
+        default_graph: Vec<T::OwnedTriple>,
+    },
+}
+
+struct Collect<T: ToOwnedRdf> {
+    triples: Vec<T::OwnedTriple>,
+    quads: Vec<OwnedQuad<T>>,
+}
+
+impl<T: ToOwnedRdf> RdfHandler<T> for Collect<T> {
+    fn handle_triple<'b>(&mut self, triple: T::Triple<'b>) {
+        self.triples.push(T::owned_triple(triple));
+    }
+
+    fn handle_quad<'b>(&mut self, quad: T::Quad<'b>) {
+        self.quads.push(T::owned_quad(quad));
+    }
+}
+
+impl<T: ToOwnedRdf> Deserializer<T>
+where
+    T::Term: PartialEq,
+{
+    /// Decodes a frame and groups its statements by the logical stream type.
+    ///
+    /// Streams without logical type are read as flat triples or flat quads, depending on
+    /// their physical type.
+    pub fn logical_frame(
+        &mut self,
+        frame: RdfStreamFrame,
+    ) -> Result<LogicalFrame<T>, DeserializeError> {
+        let Collect { triples, quads } = self.handle_frame(
+            frame,
+            Collect {
+                triples: Vec::new(),
+                quads: Vec::new(),
+            },
+        )?;
+        let inner = self.inner().ok_or(ConfigError::NotSet)?;
+
+        Ok(match inner.logical_type() {
+            LogicalStreamType::Unspecified
+                if inner.physical_type() == PhysicalStreamType::Triples =>
+            {
+                LogicalFrame::Triples(triples)
+            }
+            LogicalStreamType::Unspecified => LogicalFrame::Quads(quads),
+            LogicalStreamType::FlatTriples => LogicalFrame::Triples(triples),
+            LogicalStreamType::FlatQuads => LogicalFrame::Quads(quads),
+            LogicalStreamType::Graphs | LogicalStreamType::SubjectGraphs => {
+                LogicalFrame::Graph(triples)
+            }
+            LogicalStreamType::Datasets => LogicalFrame::Dataset(quads),
+            logical @ (LogicalStreamType::NamedGraphs
+            | LogicalStreamType::TimestampedNamedGraphs) => named_graph(logical, quads)?,
+        })
+    }
+}
+
+fn named_graph<T: ToOwnedRdf>(
+    logical: LogicalStreamType,
+    quads: Vec<OwnedQuad<T>>,
+) -> Result<LogicalFrame<T>, DeserializeError>
+where
+    T::Term: PartialEq,
+{
+    let mut name = None;
+    let mut triples = Vec::new();
+    let mut default_graph = Vec::new();
+
+    for (triple, graph) in quads {
+        let Some(graph) = graph else {
+            default_graph.push(triple);
+            continue;
+        };
+        match &name {
+            Some(name) if *name != graph => {
+                return Err(DeserializeError::MultipleNamedGraphs(logical));
+            }
+            Some(_) => {}
+            None => name = Some(graph),
+        }
+        triples.push(triple);
+    }
+
+    if name.is_none() {
+        triples = std::mem::take(&mut default_graph);
+    }
+    Ok(LogicalFrame::NamedGraph {
+        name,
+        triples,
+        default_graph,
+    })
+}
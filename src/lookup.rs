@@ -1,6 +1,8 @@
 use std::{
     borrow::Cow,
+    collections::HashMap,
     ops::{Index, IndexMut},
+    sync::{Arc, LazyLock},
 };
 
 use crate::error::LookupError;
@@ -11,6 +13,11 @@ pub struct Lookup {
     next_written: usize,
     last_read: usize,
     arr: Vec<Option<Cow<'static, str>>>,
+    // Shared copies of the entries, made on first use by backends whose terms hold on to
+    // entries
+    shared: Vec<Option<Arc<str>>>,
+    // Bumped whenever an entry is written, to tell overwritten entries apart
+    generations: Vec<u32>,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -32,12 +39,15 @@ impl LookupType {
 }
 
 static EMPTY: Cow<'static, str> = Cow::Borrowed("");
+static EMPTY_SHARED: LazyLock<Arc<str>> = LazyLock::new(|| Arc::from(""));
 
 impl Lookup {
     pub fn new(size: u32) -> Self {
         Self {
             // This is size+1 because index 0 is ignored, never read/written
             arr: vec![None; size as usize + 1],
+            shared: vec![None; size as usize + 1],
+            generations: vec![0; size as usize + 1],
             last_read: 0,
             next_written: 1,
             size: size as usize,
@@ -45,6 +55,28 @@ impl Lookup {
     }
 
     pub fn get(&mut self, index: u32, ty: LookupType) -> Result<&Cow<'static, str>, LookupError> {
+        let id = self.resolve(index, ty)?;
+        Ok(self.entry(id)?.0)
+    }
+
+    /// Like [`Lookup::get`], but the entry is shared instead of borrowed. The entry is
+    /// copied once, when it is first shared.
+    pub fn get_shared(&mut self, index: u32, ty: LookupType) -> Result<&Arc<str>, LookupError> {
+        let id = self.resolve(index, ty)?;
+        if self.size == 0 {
+            return Ok(&EMPTY_SHARED);
+        }
+        let value = self
+            .arr
+            .get(id)
+            .and_then(|x| x.as_deref())
+            .ok_or(LookupError::Missing(id, self.arr.len()))?;
+        Ok(self.shared[id].get_or_insert_with(|| Arc::from(value)))
+    }
+
+    /// Resolves a delta encoded `index` to the id of the entry it refers to, the way
+    /// [`Lookup::get`] does.
+    pub fn resolve(&mut self, index: u32, ty: LookupType) -> Result<usize, LookupError> {
         trace!(
             "Lookup index {} size {} arr len {}",
             index,
@@ -52,7 +84,7 @@ impl Lookup {
             self.arr.len()
         );
         if self.size == 0 {
-            return Ok(&EMPTY);
+            return Ok(0);
         }
         if index as usize > self.size {
             trace!("LookupTableTooSmall");
@@ -64,10 +96,31 @@ impl Lookup {
         }
 
         self.last_read = id;
+        Ok(id)
+    }
+
+    /// The entry with the resolved `id` and its generation, which changes every time the
+    /// entry is overwritten.
+    pub fn entry(&self, id: usize) -> Result<(&Cow<'static, str>, u32), LookupError> {
+        if self.size == 0 {
+            return Ok((&EMPTY, 0));
+        }
         self.arr
             .get(id)
             .and_then(|x| x.as_ref())
-            .ok_or_else(|| LookupError::Missing(id, self.arr.len()))
+            .map(|value| (value, self.generations[id]))
+            .ok_or(LookupError::Missing(id, self.arr.len()))
+    }
+
+    /// Grows the table to `size` entries, keeping the current ones.
+    pub(crate) fn grow(&mut self, size: u32) {
+        let size = size as usize;
+        if size > self.size {
+            self.arr.resize(size + 1, None);
+            self.shared.resize(size + 1, None);
+            self.generations.resize(size + 1, 0);
+            self.size = size;
+        }
     }
 
     pub fn set(&mut self, index: u32, str: String) -> Result<(), LookupError> {
@@ -81,6 +134,8 @@ impl Lookup {
         }
 
         self.arr[id] = Some(Cow::Owned(str));
+        self.shared[id] = None;
+        self.generations[id] = self.generations[id].wrapping_add(1);
         Ok(())
     }
 }
@@ -95,6 +150,213 @@ impl Index<usize> for Lookup {
 
 impl IndexMut<usize> for Lookup {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        return &mut self.arr[index - 1];
+        &mut self.arr[index - 1]
+    }
+}
+
+/// Terms built from a prefix and a name entry, keyed by the ids of both entries.
+///
+/// Each term remembers the generations of the entries it was built from, see
+/// [`Lookup::entry`], so it is not returned anymore once either entry was overwritten.
+#[derive(Debug, Clone)]
+pub struct IriCache<T> {
+    // One term per name id, as names mostly come with the same prefix
+    terms: Vec<Option<CachedIri<T>>>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedIri<T> {
+    prefix_id: usize,
+    generations: (u32, u32),
+    // Only kept once the term is built a second time
+    term: Option<T>,
+}
+
+impl<T> IriCache<T> {
+    /// Cache for a name table of `size` entries.
+    pub fn new(size: u32) -> Self {
+        Self {
+            terms: (0..=size).map(|_| None).collect(),
+        }
+    }
+
+    /// Grows the cache along with its name table.
+    pub(crate) fn grow(&mut self, size: u32) {
+        let len = size as usize + 1;
+        if len > self.terms.len() {
+            self.terms.resize_with(len, || None);
+        }
+    }
+
+    /// The term built from the given entries, built with `build` unless it was kept from
+    /// before. Terms are kept once they are built a second time, so IRIs that are used only
+    /// once are not copied into the cache.
+    pub fn get_or_try_build<E>(
+        &mut self,
+        (prefix_id, name_id): (usize, usize),
+        generations: (u32, u32),
+        build: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E>
+    where
+        T: Clone,
+    {
+        let slot = &mut self.terms[name_id];
+        match slot {
+            Some(cached) if cached.prefix_id == prefix_id && cached.generations == generations => {
+                if let Some(term) = &cached.term {
+                    return Ok(term.clone());
+                }
+                let term = build()?;
+                cached.term = Some(term.clone());
+                Ok(term)
+            }
+            _ => {
+                let term = build()?;
+                *slot = Some(CachedIri {
+                    prefix_id,
+                    generations,
+                    term: None,
+                });
+                Ok(term)
+            }
+        }
+    }
+}
+
+/// Id to reference an entry of a [`LookupEncoder`] with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EncodedLookup {
+    /// Id to use in the term, 0 when the [`LookupType`] allows it
+    pub id: u32,
+    /// Id of the entry row that has to be emitted before the term, if the value is new
+    pub new_entry: Option<u32>,
+}
+
+/// Encoding side of [`Lookup`], assigns ids to values and evicts the least recently used
+/// entry once the table is full.
+///
+/// Ids are delta encoded the way [`Lookup::get`] and [`Lookup::set`] expect them, so the
+/// encoder has to see values in the same order as the decoder will.
+#[derive(Debug, Clone)]
+pub struct LookupEncoder {
+    size: usize,
+    ids: HashMap<String, usize>,
+    // Index 0 is the head of the recently used list, never holds a value
+    values: Vec<Option<String>>,
+    prev: Vec<usize>,
+    next: Vec<usize>,
+    // Statement that last used the entry, these can not be evicted
+    used_in: Vec<u64>,
+    statement: u64,
+
+    next_free: usize,
+    last_written: usize,
+    last_read: usize,
+    statement_last_read: usize,
+}
+
+impl LookupEncoder {
+    pub fn new(size: u32) -> Self {
+        let slots = size as usize + 1;
+        Self {
+            size: size as usize,
+            ids: HashMap::new(),
+            values: vec![None; slots],
+            prev: vec![0; slots],
+            next: vec![0; slots],
+            used_in: vec![0; slots],
+            statement: 1,
+
+            next_free: 1,
+            last_written: 0,
+            last_read: 0,
+            statement_last_read: 0,
+        }
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size as u32
+    }
+
+    /// Marks the start of a new statement, entries used by a statement are never evicted
+    /// while encoding that same statement.
+    pub fn start_statement(&mut self) {
+        self.statement += 1;
+        self.statement_last_read = self.last_read;
+    }
+
+    /// Forgets the ids referenced since [`LookupEncoder::start_statement`], for statements
+    /// that will not be emitted. Entries that were created stay valid.
+    pub fn abort_statement(&mut self) {
+        self.last_read = self.statement_last_read;
+    }
+
+    fn unlink(&mut self, id: usize) {
+        let (prev, next) = (self.prev[id], self.next[id]);
+        self.next[prev] = next;
+        self.prev[next] = prev;
+    }
+
+    fn push_back(&mut self, id: usize) {
+        let last = self.prev[0];
+        self.next[last] = id;
+        self.prev[id] = last;
+        self.next[id] = 0;
+        self.prev[0] = id;
+        self.used_in[id] = self.statement;
+    }
+
+    fn insert(&mut self, value: &str) -> Result<u32, LookupError> {
+        let id = if self.next_free <= self.size {
+            self.next_free += 1;
+            self.next_free - 1
+        } else {
+            let victim = self.next[0];
+            if victim == 0 || self.used_in[victim] == self.statement {
+                return Err(LookupError::LookupTableTooSmall(self.size));
+            }
+            self.unlink(victim);
+            if let Some(old) = self.values[victim].take() {
+                self.ids.remove(&old);
+            }
+            victim
+        };
+
+        self.values[id] = Some(value.to_string());
+        self.ids.insert(value.to_string(), id);
+        self.push_back(id);
+
+        let entry = if id == self.last_written + 1 { 0 } else { id };
+        self.last_written = id;
+        Ok(entry as u32)
+    }
+
+    pub fn encode(&mut self, value: &str, ty: LookupType) -> Result<EncodedLookup, LookupError> {
+        if self.size == 0 {
+            return Err(LookupError::LookupFromEmptyTable);
+        }
+        let (id, new_entry) = match self.ids.get(value) {
+            Some(&id) => {
+                self.unlink(id);
+                self.push_back(id);
+                (id, None)
+            }
+            None => {
+                let entry = self.insert(value)?;
+                (self.prev[0], Some(entry))
+            }
+        };
+
+        let encoded = match ty {
+            LookupType::Inc if id == self.last_read + 1 => 0,
+            LookupType::Stay if id == self.last_read => 0,
+            _ => id,
+        };
+        self.last_read = id;
+
+        Ok(EncodedLookup {
+            id: encoded as u32,
+            new_entry,
+        })
     }
 }
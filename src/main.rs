@@ -8,10 +8,10 @@ use jelly::{
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init(); // Initialize logger, reads RUST_LOG env var
-    let args: Vec<_> = std::env::args().into_iter().collect::<Vec<_>>();
+    let args: Vec<_> = std::env::args().collect::<Vec<_>>();
     let file_name = args.get(1).expect("A file argument");
     let file = File::open(file_name)?;
-    let generator = FrameReader::new(file);
+    let generator = FrameReader::auto(file)?;
 
     let mut des = Deserializer::<StringRdf>::new();
 
@@ -26,7 +26,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         },
     );
     for frame in generator {
-        des.handle_frame(frame, &mut h)?;
+        des.handle_frame(frame?, &mut h)?;
     }
 
     Ok(())
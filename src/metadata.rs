@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::MetadataError;
+
+/// Typed access to the metadata of a frame, which maps keys to raw bytes.
+///
+/// Strings are stored as UTF-8, integers as 8 bytes big endian and timestamps as
+/// milliseconds since the Unix epoch in the same format as signed integers.
+///
+/// The Jelly specification leaves the bytes of metadata entries to the application, this
+/// encoding is specific to this crate. Other implementations do not read or write these
+/// typed entries the same way, so use them only when both ends of a stream use this
+/// crate, and use raw bytes otherwise.
+pub trait FrameMetadata {
+    fn get_str(&self, key: &str) -> Result<Option<&str>, MetadataError>;
+    fn get_u64(&self, key: &str) -> Result<Option<u64>, MetadataError>;
+    fn get_timestamp(&self, key: &str) -> Result<Option<SystemTime>, MetadataError>;
+
+    fn insert_str(&mut self, key: impl Into<String>, value: &str);
+    fn insert_u64(&mut self, key: impl Into<String>, value: u64);
+    fn insert_timestamp(&mut self, key: impl Into<String>, value: SystemTime);
+}
+
+impl FrameMetadata for HashMap<String, Vec<u8>> {
+    fn get_str(&self, key: &str) -> Result<Option<&str>, MetadataError> {
+        let Some(value) = self.get(key) else {
+            return Ok(None);
+        };
+        std::str::from_utf8(value)
+            .map(Some)
+            .map_err(|_| MetadataError::InvalidUtf8 {
+                key: key.to_string(),
+            })
+    }
+
+    fn get_u64(&self, key: &str) -> Result<Option<u64>, MetadataError> {
+        let Some(value) = self.get(key) else {
+            return Ok(None);
+        };
+        let bytes =
+            <[u8; 8]>::try_from(value.as_slice()).map_err(|_| MetadataError::InvalidLength {
+                key: key.to_string(),
+                len: value.len(),
+                expected: 8,
+            })?;
+        Ok(Some(u64::from_be_bytes(bytes)))
+    }
+
+    fn get_timestamp(&self, key: &str) -> Result<Option<SystemTime>, MetadataError> {
+        Ok(self.get_u64(key)?.map(|millis| {
+            let millis = millis as i64;
+            let since_epoch = Duration::from_millis(millis.unsigned_abs());
+            if millis < 0 {
+                UNIX_EPOCH - since_epoch
+            } else {
+                UNIX_EPOCH + since_epoch
+            }
+        }))
+    }
+
+    fn insert_str(&mut self, key: impl Into<String>, value: &str) {
+        self.insert(key.into(), value.as_bytes().to_vec());
+    }
+
+    fn insert_u64(&mut self, key: impl Into<String>, value: u64) {
+        self.insert(key.into(), value.to_be_bytes().to_vec());
+    }
+
+    fn insert_timestamp(&mut self, key: impl Into<String>, value: SystemTime) {
+        let millis = match value.duration_since(UNIX_EPOCH) {
+            Ok(after) => after.as_millis() as i64,
+            Err(before) => -(before.duration().as_millis() as i64),
+        };
+        self.insert_u64(key, millis as u64);
+    }
+}
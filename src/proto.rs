@@ -3,6 +3,13 @@ include!(concat!(
     "/eu.ostrzyciel.jelly.core.proto.v1.rs"
 ));
 
+/// Protocol version written by Jelly 1.0.x
+pub const PROTO_VERSION_1_0_X: u32 = 1;
+/// Protocol version written by Jelly 1.1.x, which adds namespace declarations
+pub const PROTO_VERSION_1_1_X: u32 = 2;
+/// Newest protocol version this crate reads
+pub const PROTO_VERSION: u32 = PROTO_VERSION_1_1_X;
+
 pub mod patch {
     include!(concat!(
         env!("OUT_DIR"),
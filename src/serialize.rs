@@ -0,0 +1,601 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::error::{
+    ConfigError, MessageType, PhysicalStreamError, SerializeError, Table, TermKind, TermLocation,
+};
+use crate::from_rdf::{AsTermRef, LiteralKind, Term, TermRef};
+use crate::lookup::{LookupEncoder, LookupType};
+use crate::proto::rdf_literal::LiteralKind as ProtoLiteralKind;
+use crate::proto::rdf_stream_row::Row;
+use crate::proto::{
+    PROTO_VERSION_1_0_X, PhysicalStreamType, RdfDatatypeEntry, RdfDefaultGraph, RdfGraphEnd,
+    RdfGraphStart, RdfIri, RdfLiteral, RdfNameEntry, RdfPrefixEntry, RdfQuad, RdfStreamFrame,
+    RdfStreamOptions, RdfStreamRow, RdfTriple, rdf_graph_start as gs, rdf_quad as q,
+    rdf_triple as t,
+};
+use paste::paste;
+use prost::Message as _;
+
+const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+const MIN_NAME_TABLE_SIZE: u32 = 8;
+
+/// Splits an IRI after the last `/` or `#`, the part before is used as prefix.
+fn split_iri(iri: &str) -> (&str, &str) {
+    match iri.rfind(['/', '#']) {
+        Some(at) => iri.split_at(at + 1),
+        None => ("", iri),
+    }
+}
+
+pub trait EncodeTerm<Term> {
+    fn encode_term(&mut self, term: &TermRef<'_>) -> Result<Term, SerializeError>;
+}
+
+macro_rules! implEncodeTerm {
+    ($k:path, $letter:ident, $location:ident, $($extra:path)?) => {
+        paste! {
+                impl EncodeTerm<$k> for Serializer {
+                    #[inline]
+                    fn encode_term(&mut self, term: &TermRef<'_>) -> Result<$k, SerializeError> {
+                        self.check_term(TermLocation::$location, term)?;
+                        Ok(match term {
+                            Term::Iri(iri) => $k::[<$letter Iri>](self.iri(iri)?),
+                            Term::BlankNode(id) => $k::[<$letter Bnode>](id.to_string()),
+                            Term::Literal(lex, kind) => $k::[<$letter Literal>](self.literal(lex, kind)?),
+                            Term::Triple(triple) => $k::[<$letter TripleTerm>]($($extra)?(self.term_triple(triple)?)),
+                        })
+                    }
+                }
+        }
+    };
+}
+
+implEncodeTerm!(t::Subject, S, Subject, Box::new);
+implEncodeTerm!(t::Predicate, P, Predicate, Box::new);
+implEncodeTerm!(t::Object, O, Object, Box::new);
+implEncodeTerm!(q::Subject, S, Subject,);
+implEncodeTerm!(q::Predicate, P, Predicate,);
+implEncodeTerm!(q::Object, O, Object,);
+
+type Quad<T> = (T, T, T, Option<T>);
+
+fn term_ref<T: AsTermRef + ?Sized>(term: &T) -> Result<TermRef<'_>, SerializeError> {
+    term.as_term_ref().ok_or(SerializeError::UnsupportedTerm)
+}
+
+/// Decides where the [`Serializer`] closes frames.
+///
+/// Frames are only closed between statements, so lookup entries always end up in the same
+/// frame as the first statement using them and every frame can be decoded given the frames
+/// before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FramingPolicy {
+    /// Frames are only closed by [`Serializer::flush`]
+    #[default]
+    Manual,
+    /// Close the frame once it holds at least this many rows
+    Rows(usize),
+    /// Close the frame once its rows take roughly this many bytes encoded
+    Bytes(usize),
+    /// Close the frame after every graph of a `Graphs` stream and before a quad of another
+    /// graph in a `Quads` stream, so every frame holds one graph
+    PerGraph,
+    /// Close the frame after every [`Serializer::dataset`], so every frame holds one dataset
+    PerDataset,
+}
+
+/// Encodes statements into [`RdfStreamFrame`]s.
+///
+/// Rows are collected into the current frame until the [`FramingPolicy`] closes it or
+/// [`Serializer::flush`] is called, finished frames are taken out with
+/// [`Serializer::frames`] or [`Serializer::finish`].
+pub struct Serializer {
+    physical_type: PhysicalStreamType,
+    rdf_star: bool,
+    generalized_statements: bool,
+    // The graph opened with the last graph start row in a `Graphs` stream,
+    // `Some(None)` is the default graph
+    current_graph: Option<Option<Term>>,
+
+    // Terms of the previous statement, repeated terms are left out of the next one
+    elide_repeated_terms: bool,
+    last_terms: [Option<Term>; 3],
+    last_graph: Option<Option<Term>>,
+
+    name_table: LookupEncoder,
+    prefix_table: LookupEncoder,
+    datatype_table: LookupEncoder,
+
+    framing: FramingPolicy,
+    rows: Vec<RdfStreamRow>,
+    rows_len: usize,
+    // Attached to the current frame when it is closed
+    metadata: HashMap<String, Vec<u8>>,
+    frames: VecDeque<RdfStreamFrame>,
+}
+
+impl Serializer {
+    /// Options with the table sizes commonly used by other Jelly implementations.
+    ///
+    /// RDF-star and generalized statements are disabled, the serializer rejects terms that
+    /// need them unless `rdf_star` or `generalized_statements` is set.
+    pub fn default_options(physical_type: PhysicalStreamType) -> RdfStreamOptions {
+        RdfStreamOptions {
+            physical_type: physical_type as i32,
+            max_name_table_size: 4000,
+            max_prefix_table_size: 150,
+            max_datatype_table_size: 32,
+            version: PROTO_VERSION_1_0_X,
+            ..Default::default()
+        }
+    }
+
+    pub fn new(mut options: RdfStreamOptions) -> Result<Self, ConfigError> {
+        info!("Options {:?}", options);
+        let physical_type = PhysicalStreamType::try_from(options.physical_type)?;
+        if physical_type == PhysicalStreamType::Unspecified {
+            return Err(ConfigError::InvalidPhysicalType(physical_type));
+        }
+        ConfigError::logical_type(options.logical_type, physical_type)?;
+        if options.max_name_table_size < MIN_NAME_TABLE_SIZE {
+            return Err(ConfigError::TableTooSmall {
+                table: Table::NameTable,
+                set: options.max_name_table_size,
+                min: MIN_NAME_TABLE_SIZE,
+            });
+        }
+        if let Some(table_error) = ConfigError::name_table(options.max_name_table_size) {
+            return Result::Err(table_error);
+        };
+
+        if let Some(table_error) = ConfigError::prefix_table(options.max_prefix_table_size) {
+            return Result::Err(table_error);
+        };
+
+        if let Some(table_error) = ConfigError::datatype_table(options.max_datatype_table_size) {
+            return Result::Err(table_error);
+        };
+
+        if options.version == 0 {
+            options.version = PROTO_VERSION_1_0_X;
+        }
+        if let Some(version_error) = ConfigError::version(options.version) {
+            return Err(version_error);
+        }
+
+        let mut serializer = Self {
+            physical_type,
+            rdf_star: options.rdf_star,
+            generalized_statements: options.generalized_statements,
+            current_graph: None,
+
+            elide_repeated_terms: true,
+            last_terms: [None, None, None],
+            last_graph: None,
+
+            name_table: LookupEncoder::new(options.max_name_table_size),
+            prefix_table: LookupEncoder::new(options.max_prefix_table_size),
+            datatype_table: LookupEncoder::new(options.max_datatype_table_size),
+
+            framing: FramingPolicy::default(),
+            rows: Vec::new(),
+            rows_len: 0,
+            metadata: HashMap::new(),
+            frames: VecDeque::new(),
+        };
+        serializer.push(Row::Options(options));
+        Ok(serializer)
+    }
+
+    /// Sets where frames are closed, [`FramingPolicy::Manual`] by default.
+    pub fn set_framing_policy(&mut self, framing: FramingPolicy) {
+        self.framing = framing;
+    }
+
+    /// Metadata of the current frame, it is attached when the frame is closed and starts
+    /// out empty for the next one.
+    ///
+    /// Use [`crate::metadata::FrameMetadata`] to add typed entries.
+    pub fn frame_metadata_mut(&mut self) -> &mut HashMap<String, Vec<u8>> {
+        &mut self.metadata
+    }
+
+    /// Enables or disables leaving out terms that are the same as in the previous
+    /// statement (enabled by default). Disabling it makes every row readable on its own,
+    /// which helps when debugging a stream.
+    pub fn set_repeated_term_elision(&mut self, enabled: bool) {
+        self.elide_repeated_terms = enabled;
+    }
+
+    /// Which terms are the same as in the previous statement and can be left out.
+    fn repeated(&self, terms: &[TermRef<'_>; 3]) -> [bool; 3] {
+        std::array::from_fn(|i| {
+            self.elide_repeated_terms
+                && self.last_terms[i]
+                    .as_ref()
+                    .is_some_and(|last| last.borrowed() == terms[i])
+        })
+    }
+
+    fn remember(&mut self, terms: &[TermRef<'_>; 3], repeated: [bool; 3]) {
+        for ((last, term), repeated) in self.last_terms.iter_mut().zip(terms).zip(repeated) {
+            if !repeated {
+                *last = Some(term.to_owned_term());
+            }
+        }
+    }
+
+    #[inline]
+    fn unless_repeated<K>(
+        &mut self,
+        term: &TermRef<'_>,
+        repeated: bool,
+    ) -> Result<Option<K>, SerializeError>
+    where
+        Self: EncodeTerm<K>,
+    {
+        if repeated {
+            return Ok(None);
+        }
+        self.encode_term(term).map(Some)
+    }
+
+    /// Encodes a single statement, lookup ids referenced by a failed statement are
+    /// forgotten so the next statement is still encoded relative to the last emitted one.
+    fn statement<R>(
+        &mut self,
+        encode: impl FnOnce(&mut Self) -> Result<R, SerializeError>,
+    ) -> Result<R, SerializeError> {
+        self.name_table.start_statement();
+        self.prefix_table.start_statement();
+        self.datatype_table.start_statement();
+
+        let out = encode(self);
+        if out.is_err() {
+            self.name_table.abort_statement();
+            self.prefix_table.abort_statement();
+            self.datatype_table.abort_statement();
+        }
+        out
+    }
+
+    #[inline]
+    fn push(&mut self, row: Row) {
+        let row = RdfStreamRow { row: Some(row) };
+        // Field key and length prefix of the row in the frame, kept for every policy as
+        // it can be changed while a frame is open
+        let len = row.encoded_len();
+        self.rows_len += 1 + prost::length_delimiter_len(len) + len;
+        self.rows.push(row);
+    }
+
+    /// Pushes a statement (or graph end) row and closes the frame if the policy says so.
+    fn push_statement(&mut self, row: Row) {
+        let graph_end = matches!(row, Row::GraphEnd(_));
+        self.push(row);
+
+        let close = match self.framing {
+            FramingPolicy::Manual | FramingPolicy::PerDataset => false,
+            FramingPolicy::Rows(rows) => self.rows.len() >= rows,
+            FramingPolicy::Bytes(bytes) => self.rows_len >= bytes,
+            FramingPolicy::PerGraph => graph_end,
+        };
+        if close {
+            self.flush();
+        }
+    }
+
+    /// Checks that the stream options allow `term` in `location`.
+    fn check_term(&self, location: TermLocation, term: &TermRef<'_>) -> Result<(), SerializeError> {
+        let kind = match term {
+            Term::Iri(_) => return Ok(()),
+            Term::BlankNode(_) => TermKind::BlankNode,
+            Term::Literal(..) => TermKind::Literal,
+            Term::Triple(_) => TermKind::TripleTerm,
+        };
+        if kind == TermKind::TripleTerm && !self.rdf_star {
+            return Err(SerializeError::RdfStarNotEnabled(location));
+        }
+        if !self.generalized_statements && !location.allows(kind) {
+            return Err(SerializeError::GeneralizedNotEnabled { location, kind });
+        }
+        Ok(())
+    }
+
+    fn iri(&mut self, iri: &str) -> Result<RdfIri, SerializeError> {
+        if self.prefix_table.size() == 0 {
+            return Ok(RdfIri {
+                prefix_id: 0,
+                name_id: self.name(iri)?,
+            });
+        }
+
+        let (prefix, name) = split_iri(iri);
+        let encoded = self.prefix_table.encode(prefix, LookupType::Stay)?;
+        if let Some(id) = encoded.new_entry {
+            self.push(Row::Prefix(RdfPrefixEntry {
+                id,
+                value: prefix.to_string(),
+            }));
+        }
+
+        Ok(RdfIri {
+            prefix_id: encoded.id,
+            name_id: self.name(name)?,
+        })
+    }
+
+    fn name(&mut self, name: &str) -> Result<u32, SerializeError> {
+        let encoded = self.name_table.encode(name, LookupType::Inc)?;
+        if let Some(id) = encoded.new_entry {
+            self.push(Row::Name(RdfNameEntry {
+                id,
+                value: name.to_string(),
+            }));
+        }
+        Ok(encoded.id)
+    }
+
+    fn literal(
+        &mut self,
+        lex: &str,
+        kind: &LiteralKind<&str>,
+    ) -> Result<RdfLiteral, SerializeError> {
+        let literal_kind = match kind {
+            LiteralKind::Simple => None,
+            LiteralKind::Datatype(datatype) if *datatype == XSD_STRING => None,
+            LiteralKind::Language(tag) => Some(ProtoLiteralKind::Langtag(tag.to_string())),
+            LiteralKind::Datatype(datatype) => {
+                if self.datatype_table.size() == 0 {
+                    return Err(SerializeError::TableDisabled(Table::DatatypeTable));
+                }
+                let encoded = self.datatype_table.encode(datatype, LookupType::Invalid)?;
+                if let Some(id) = encoded.new_entry {
+                    self.push(Row::Datatype(RdfDatatypeEntry {
+                        id,
+                        value: datatype.to_string(),
+                    }));
+                }
+                Some(ProtoLiteralKind::Datatype(encoded.id))
+            }
+        };
+
+        Ok(RdfLiteral {
+            lex: lex.to_string(),
+            literal_kind,
+        })
+    }
+
+    fn term_triple(&mut self, triple: &[TermRef<'_>; 3]) -> Result<RdfTriple, SerializeError> {
+        Ok(RdfTriple {
+            subject: Some(self.encode_term(&triple[0])?),
+            predicate: Some(self.encode_term(&triple[1])?),
+            object: Some(self.encode_term(&triple[2])?),
+        })
+    }
+
+    fn graph(&mut self, graph: Option<&TermRef<'_>>) -> Result<q::Graph, SerializeError> {
+        if let Some(graph @ Term::Literal(..)) = graph {
+            self.check_term(TermLocation::Graph, graph)?;
+        }
+        Ok(match graph {
+            None => q::Graph::GDefaultGraph(RdfDefaultGraph {}),
+            Some(Term::Iri(iri)) => q::Graph::GIri(self.iri(iri)?),
+            Some(Term::BlankNode(id)) => q::Graph::GBnode(id.to_string()),
+            Some(Term::Literal(lex, kind)) => q::Graph::GLiteral(self.literal(lex, kind)?),
+            Some(Term::Triple(_)) => return Err(SerializeError::UnsupportedTerm),
+        })
+    }
+
+    fn incorrect_type(&self, incoming: MessageType) -> SerializeError {
+        SerializeError::PhysicalStreamError(PhysicalStreamError::IncorrectType {
+            detected: self.physical_type,
+            incoming,
+        })
+    }
+
+    /// Adds a triple to the current frame, preceded by the lookup entries it needs.
+    ///
+    /// In a `Graphs` stream the triple belongs to the graph opened with
+    /// [`Serializer::start_graph`].
+    pub fn triple<S, P, O>(
+        &mut self,
+        subject: &S,
+        predicate: &P,
+        object: &O,
+    ) -> Result<(), SerializeError>
+    where
+        S: AsTermRef + ?Sized,
+        P: AsTermRef + ?Sized,
+        O: AsTermRef + ?Sized,
+    {
+        match self.physical_type {
+            PhysicalStreamType::Triples => {}
+            PhysicalStreamType::Graphs if self.current_graph.is_some() => {}
+            PhysicalStreamType::Graphs => {
+                return Err(SerializeError::PhysicalStreamError(
+                    PhysicalStreamError::NotYetSet {
+                        detected: self.physical_type,
+                        expected: MessageType::GraphStart,
+                    },
+                ));
+            }
+            _ => return Err(self.incorrect_type(MessageType::Triple)),
+        }
+
+        let triple = [term_ref(subject)?, term_ref(predicate)?, term_ref(object)?];
+        let repeated = self.repeated(&triple);
+        let row = self.statement(|this| {
+            Ok(RdfTriple {
+                subject: this.unless_repeated(&triple[0], repeated[0])?,
+                predicate: this.unless_repeated(&triple[1], repeated[1])?,
+                object: this.unless_repeated(&triple[2], repeated[2])?,
+            })
+        })?;
+        self.remember(&triple, repeated);
+        self.push_statement(Row::Triple(row));
+        Ok(())
+    }
+
+    /// Adds a quad to the current frame, `None` is the default graph.
+    ///
+    /// In a `Graphs` stream the graph is started when it differs from the current one,
+    /// so consecutive quads of the same graph end up in one graph.
+    pub fn quad<S, P, O, G>(
+        &mut self,
+        subject: &S,
+        predicate: &P,
+        object: &O,
+        graph: Option<&G>,
+    ) -> Result<(), SerializeError>
+    where
+        S: AsTermRef + ?Sized,
+        P: AsTermRef + ?Sized,
+        O: AsTermRef + ?Sized,
+        G: AsTermRef + ?Sized,
+    {
+        match self.physical_type {
+            PhysicalStreamType::Quads => {
+                let quad = [term_ref(subject)?, term_ref(predicate)?, term_ref(object)?];
+                let graph = graph.map(term_ref).transpose()?;
+                let same_graph = self
+                    .last_graph
+                    .as_ref()
+                    .map(|last| last.as_ref().map(Term::borrowed).as_ref() == graph.as_ref());
+                if self.framing == FramingPolicy::PerGraph && same_graph == Some(false) {
+                    self.flush();
+                }
+                let repeated = self.repeated(&quad);
+                let repeated_graph = self.elide_repeated_terms && same_graph == Some(true);
+
+                let row = self.statement(|this| {
+                    Ok(RdfQuad {
+                        subject: this.unless_repeated(&quad[0], repeated[0])?,
+                        predicate: this.unless_repeated(&quad[1], repeated[1])?,
+                        object: this.unless_repeated(&quad[2], repeated[2])?,
+                        graph: if repeated_graph {
+                            None
+                        } else {
+                            Some(this.graph(graph.as_ref())?)
+                        },
+                    })
+                })?;
+                self.remember(&quad, repeated);
+                if !repeated_graph {
+                    self.last_graph = Some(graph.map(|graph| graph.to_owned_term()));
+                }
+                self.push_statement(Row::Quad(row));
+                Ok(())
+            }
+            PhysicalStreamType::Graphs => {
+                let same_graph = match (&self.current_graph, graph) {
+                    (Some(None), None) => true,
+                    (Some(Some(current)), Some(graph)) => {
+                        Some(current.borrowed()) == graph.as_term_ref()
+                    }
+                    _ => false,
+                };
+                if !same_graph {
+                    self.start_graph(graph)?;
+                }
+                self.triple(subject, predicate, object)
+            }
+            _ => Err(self.incorrect_type(MessageType::Quad)),
+        }
+    }
+
+    /// Starts a new graph in a `Graphs` stream, ending the current one if there is one.
+    pub fn start_graph<G: AsTermRef + ?Sized>(
+        &mut self,
+        graph: Option<&G>,
+    ) -> Result<(), SerializeError> {
+        if self.physical_type != PhysicalStreamType::Graphs {
+            return Err(self.incorrect_type(MessageType::GraphStart));
+        }
+        let graph = graph.map(term_ref).transpose()?;
+        self.end_graph()?;
+
+        let start = self.statement(|this| this.graph(graph.as_ref()))?;
+        let start = match start {
+            q::Graph::GIri(iri) => gs::Graph::GIri(iri),
+            q::Graph::GBnode(id) => gs::Graph::GBnode(id),
+            q::Graph::GDefaultGraph(default) => gs::Graph::GDefaultGraph(default),
+            q::Graph::GLiteral(literal) => gs::Graph::GLiteral(literal),
+        };
+        self.push(Row::GraphStart(RdfGraphStart { graph: Some(start) }));
+        self.current_graph = Some(graph.map(|graph| graph.to_owned_term()));
+        Ok(())
+    }
+
+    /// Ends the current graph in a `Graphs` stream, does nothing if no graph is open.
+    pub fn end_graph(&mut self) -> Result<(), SerializeError> {
+        if self.physical_type != PhysicalStreamType::Graphs {
+            return Err(self.incorrect_type(MessageType::GraphEnd));
+        }
+        if self.current_graph.take().is_some() {
+            self.push_statement(Row::GraphEnd(RdfGraphEnd {}));
+        }
+        Ok(())
+    }
+
+    /// Adds all quads, grouped by graph in the order the graphs are first seen.
+    ///
+    /// This buffers the quads, but makes sure every graph is only started once in a
+    /// `Graphs` stream.
+    pub fn dataset<I, T>(&mut self, quads: I) -> Result<(), SerializeError>
+    where
+        I: IntoIterator<Item = Quad<T>>,
+        T: AsTermRef,
+    {
+        let mut graphs: HashMap<Option<Term>, usize> = HashMap::new();
+        let mut grouped: Vec<Vec<Quad<T>>> = Vec::new();
+        for quad in quads {
+            let graph = quad.3.as_ref().map(term_ref).transpose()?;
+            let graph = graph.map(|graph| graph.to_owned_term());
+            let group = *graphs.entry(graph).or_insert_with(|| {
+                grouped.push(Vec::new());
+                grouped.len() - 1
+            });
+            grouped[group].push(quad);
+        }
+
+        for (s, p, o, g) in grouped.into_iter().flatten() {
+            self.quad(&s, &p, &o, g.as_ref())?;
+        }
+        if self.framing == FramingPolicy::PerDataset {
+            if self.current_graph.is_some() {
+                self.end_graph()?;
+            }
+            self.flush();
+        }
+        Ok(())
+    }
+
+    /// Closes the current frame, it is available from [`Serializer::frames`] afterwards.
+    pub fn flush(&mut self) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let rows = std::mem::take(&mut self.rows);
+        self.rows_len = 0;
+        self.frames.push_back(RdfStreamFrame {
+            rows,
+            metadata: std::mem::take(&mut self.metadata),
+        });
+    }
+
+    /// Takes all finished frames.
+    pub fn frames(&mut self) -> impl Iterator<Item = RdfStreamFrame> + '_ {
+        self.frames.drain(..)
+    }
+
+    /// Ends the open graph, closes the current frame and returns all frames that were
+    /// not taken yet.
+    pub fn finish(mut self) -> Vec<RdfStreamFrame> {
+        if self.current_graph.take().is_some() {
+            self.push(Row::GraphEnd(RdfGraphEnd {}));
+        }
+        self.flush();
+        self.frames.into()
+    }
+}
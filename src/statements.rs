@@ -0,0 +1,121 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::Read;
+
+use crate::deserialize::{Deserializer, RdfHandler};
+use crate::error::DeserializeError;
+use crate::io::FrameReader;
+use crate::logical::OwnedQuad;
+use crate::to_rdf::ToOwnedRdf;
+
+/// Iterates the statements of a stream as owned values, decoding one frame at a time.
+///
+/// Triples of a `Triples` stream are in the default graph. Only the statements of the
+/// current frame are buffered. After the first error the iterator ends.
+pub struct Statements<R, T: ToOwnedRdf> {
+    frames: FrameReader<R>,
+    deserializer: Deserializer<T>,
+    buffer: VecDeque<OwnedQuad<T>>,
+    metadata: HashMap<String, Vec<u8>>,
+    // The last frame had no statements, its metadata is kept for the next one
+    merge_metadata: bool,
+    done: bool,
+}
+
+struct Buffer<'a, T: ToOwnedRdf> {
+    statements: &'a mut VecDeque<OwnedQuad<T>>,
+    metadata: &'a mut HashMap<String, Vec<u8>>,
+    merge_metadata: bool,
+}
+
+impl<T: ToOwnedRdf> RdfHandler<T> for Buffer<'_, T> {
+    fn handle_triple<'b>(&mut self, triple: T::Triple<'b>) {
+        self.statements.push_back((T::owned_triple(triple), None));
+    }
+
+    fn handle_quad<'b>(&mut self, quad: T::Quad<'b>) {
+        self.statements.push_back(T::owned_quad(quad));
+    }
+
+    fn handle_frame_start(&mut self, metadata: &HashMap<String, Vec<u8>>) {
+        if !self.merge_metadata {
+            self.metadata.clear();
+        }
+        self.metadata
+            .extend(metadata.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+}
+
+impl<R, T: ToOwnedRdf> Statements<R, T> {
+    /// Reads the frames with `deserializer`, to decode with non default settings.
+    pub fn new(frames: FrameReader<R>, deserializer: Deserializer<T>) -> Self {
+        Self {
+            frames,
+            deserializer,
+            buffer: VecDeque::new(),
+            metadata: HashMap::new(),
+            merge_metadata: false,
+            done: false,
+        }
+    }
+
+    pub fn deserializer(&self) -> &Deserializer<T> {
+        &self.deserializer
+    }
+
+    /// Metadata of the frame the last returned statement comes from.
+    ///
+    /// Frames without statements add their metadata to the one of the next frame, where
+    /// the later frame wins on conflicting keys. After the end of the stream it holds the
+    /// metadata of the frames after the last statement.
+    pub fn metadata(&self) -> &HashMap<String, Vec<u8>> {
+        &self.metadata
+    }
+
+    /// Number of bytes read so far.
+    pub fn offset(&self) -> u64 {
+        self.frames.offset()
+    }
+}
+
+impl<R: Read, T: ToOwnedRdf> Iterator for Statements<R, T> {
+    type Item = Result<OwnedQuad<T>, DeserializeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(statement) = self.buffer.pop_front() {
+                return Some(Ok(statement));
+            }
+            if self.done {
+                return None;
+            }
+
+            let offset = self.frames.offset();
+            let frame = match self.frames.next()? {
+                Ok(frame) => frame,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e.into()));
+                }
+            };
+            let buffer = Buffer {
+                statements: &mut self.buffer,
+                metadata: &mut self.metadata,
+                merge_metadata: self.merge_metadata,
+            };
+            if let Err(e) = self.deserializer.handle_frame(frame, buffer) {
+                // Statements of the failed frame are dropped with it
+                self.buffer.clear();
+                self.done = true;
+                return Some(Err(e.with_offset(offset)));
+            }
+            self.merge_metadata = self.buffer.is_empty();
+        }
+    }
+}
+
+impl<R: Read> FrameReader<R> {
+    /// Decodes the statements of the stream, see [`Statements`].
+    pub fn statements<T: ToOwnedRdf>(self) -> Statements<R, T> {
+        Statements::new(self, Deserializer::new())
+    }
+}
@@ -2,13 +2,20 @@ use crate::Inner;
 use crate::error::DeserializeError;
 use crate::proto::{RdfIri, RdfLiteral, RdfTriple};
 
+mod shared;
 mod strings;
+pub use shared::{SharedLiteralKind, SharedRdf, SharedTerm};
 pub use strings::StringRdf;
 
 #[cfg(feature = "sophia")]
 mod sophia;
 #[cfg(feature = "sophia")]
-pub use sophia::SophiaRdf;
+pub use sophia::{SophiaRdf, SophiaState};
+
+#[cfg(feature = "oxrdf")]
+mod oxrdf;
+#[cfg(feature = "oxrdf")]
+pub use self::oxrdf::OxRdf;
 
 pub trait ToRdf: Sized {
     type Term;
@@ -37,3 +44,12 @@ pub trait ToRdf: Sized {
     fn triple<'b>(deserializer: &'b mut Inner<Self>) -> Result<Self::Triple<'b>, DeserializeError>;
     fn quad<'b>(deserializer: &'b mut Inner<Self>) -> Result<Self::Quad<'b>, DeserializeError>;
 }
+
+/// Backends whose statements can be kept after the handler returns.
+pub trait ToOwnedRdf: ToRdf {
+    type OwnedTriple;
+
+    fn owned_triple(triple: Self::Triple<'_>) -> Self::OwnedTriple;
+    /// Splits a quad into its triple and graph, `None` is the default graph.
+    fn owned_quad(quad: Self::Quad<'_>) -> (Self::OwnedTriple, Option<Self::Term>);
+}
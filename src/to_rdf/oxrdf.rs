@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use oxrdf::{BlankNode, GraphName, Literal, NamedNode, Quad, Subject, Term, Triple};
+
+use crate::{
+    Inner,
+    deserialize::ToTerm as _,
+    error::{DeserializeError, TermKind, TermLocation},
+    lookup::LookupType,
+    proto::{RdfIri, RdfLiteral, RdfTriple, rdf_literal::LiteralKind},
+};
+
+use super::{ToOwnedRdf, ToRdf};
+
+/// Decodes into the terms of the oxigraph ecosystem.
+///
+/// oxrdf only has IRIs as predicates and no literals as subjects or graph names, such
+/// terms of generalized streams fail with [`DeserializeError::UnsupportedTerm`]. IRIs and
+/// datatypes are validated, invalid ones fail with [`DeserializeError::OxInvalidIri`].
+pub struct OxRdf;
+
+fn kind(term: &Term) -> TermKind {
+    match term {
+        Term::NamedNode(_) => TermKind::Iri,
+        Term::BlankNode(_) => TermKind::BlankNode,
+        Term::Literal(_) => TermKind::Literal,
+        Term::Triple(_) => TermKind::TripleTerm,
+    }
+}
+
+fn unsupported(location: TermLocation, term: &Term) -> DeserializeError {
+    DeserializeError::UnsupportedTerm {
+        location,
+        kind: kind(term),
+    }
+}
+
+fn subject(term: Term) -> Result<Subject, DeserializeError> {
+    match term {
+        Term::NamedNode(iri) => Ok(Subject::NamedNode(iri)),
+        Term::BlankNode(bnode) => Ok(Subject::BlankNode(bnode)),
+        Term::Triple(triple) => Ok(Subject::Triple(triple)),
+        term => Err(unsupported(TermLocation::Subject, &term)),
+    }
+}
+
+fn predicate(term: Term) -> Result<NamedNode, DeserializeError> {
+    match term {
+        Term::NamedNode(iri) => Ok(iri),
+        term => Err(unsupported(TermLocation::Predicate, &term)),
+    }
+}
+
+fn graph_name(term: Option<Term>) -> Result<GraphName, DeserializeError> {
+    match term {
+        None => Ok(GraphName::DefaultGraph),
+        Some(Term::NamedNode(iri)) => Ok(GraphName::NamedNode(iri)),
+        Some(Term::BlankNode(bnode)) => Ok(GraphName::BlankNode(bnode)),
+        Some(term) => Err(unsupported(TermLocation::Graph, &term)),
+    }
+}
+
+fn last_triple(d: &Inner<OxRdf>) -> Result<Triple, DeserializeError> {
+    let s = d
+        .last_subject
+        .clone()
+        .ok_or(DeserializeError::MissingTerm(TermLocation::Subject))?;
+    let p = d
+        .last_predicate
+        .clone()
+        .ok_or(DeserializeError::MissingTerm(TermLocation::Predicate))?;
+    let o = d
+        .last_object
+        .clone()
+        .ok_or(DeserializeError::MissingTerm(TermLocation::Object))?;
+    Ok(Triple::new(subject(s)?, predicate(p)?, o))
+}
+
+impl ToRdf for OxRdf {
+    type Term = Term;
+
+    type Triple<'b> = Triple;
+
+    type Quad<'b> = Quad;
+
+    type State = HashMap<String, BlankNode>;
+
+    fn default_term() -> Self::Term {
+        Term::NamedNode(NamedNode::new_unchecked(""))
+    }
+
+    fn iri(iri: RdfIri, deserializer: &mut Inner<Self>) -> Result<Self::Term, DeserializeError> {
+        // Repeated IRIs are only validated once
+        deserializer.cached_iri(iri, |prefix, name| {
+            Ok(Term::NamedNode(NamedNode::new(format!(
+                "{}{}",
+                prefix, name
+            ))?))
+        })
+    }
+
+    fn bnode(key: String, deserializer: &mut Inner<Self>) -> Result<Self::Term, DeserializeError> {
+        match BlankNode::new(key.as_str()) {
+            Ok(bnode) => Ok(Term::BlankNode(bnode)),
+            // Identifiers oxrdf does not accept get a fresh one, consistent within the stream
+            Err(_) => Ok(Term::BlankNode(
+                deserializer.state.entry(key).or_default().clone(),
+            )),
+        }
+    }
+
+    fn literal(
+        literal: RdfLiteral,
+        deserializer: &mut Inner<Self>,
+    ) -> Result<Self::Term, DeserializeError> {
+        let lex = literal.lex;
+        Ok(Term::Literal(match literal.literal_kind {
+            Some(LiteralKind::Langtag(tag)) => Literal::new_language_tagged_literal(lex, tag)?,
+            Some(LiteralKind::Datatype(tag)) => {
+                let datatype = deserializer.datatype_table.get(tag, LookupType::Invalid)?;
+                Literal::new_typed_literal(lex, NamedNode::new(datatype.to_string())?)
+            }
+            None => Literal::new_simple_literal(lex),
+        }))
+    }
+
+    fn term_triple(
+        triple: RdfTriple,
+        deserializer: &mut Inner<Self>,
+    ) -> Result<Self::Term, DeserializeError> {
+        let RdfTriple {
+            subject: s,
+            predicate: p,
+            object: o,
+        } = triple;
+        let s = if let Some(s) = s {
+            deserializer.to_term(s)?
+        } else {
+            return Err(DeserializeError::MissingTermTermTriple(
+                TermLocation::Subject,
+            ));
+        };
+
+        let p = if let Some(p) = p {
+            deserializer.to_term(p)?
+        } else {
+            return Err(DeserializeError::MissingTermTermTriple(
+                TermLocation::Predicate,
+            ));
+        };
+
+        let o = if let Some(o) = o {
+            deserializer.to_term(o)?
+        } else {
+            return Err(DeserializeError::MissingTermTermTriple(
+                TermLocation::Object,
+            ));
+        };
+
+        Ok(Term::Triple(Box::new(Triple::new(
+            subject(s)?,
+            predicate(p)?,
+            o,
+        ))))
+    }
+
+    fn triple<'b>(d: &'b mut Inner<Self>) -> Result<Self::Triple<'b>, DeserializeError> {
+        last_triple(d)
+    }
+
+    fn quad<'b>(d: &'b mut Inner<Self>) -> Result<Self::Quad<'b>, DeserializeError> {
+        let Triple {
+            subject,
+            predicate,
+            object,
+        } = last_triple(d)?;
+        Ok(Quad::new(
+            subject,
+            predicate,
+            object,
+            graph_name(d.last_graph.clone())?,
+        ))
+    }
+}
+
+impl ToOwnedRdf for OxRdf {
+    type OwnedTriple = Triple;
+
+    fn owned_triple(triple: Self::Triple<'_>) -> Self::OwnedTriple {
+        triple
+    }
+
+    fn owned_quad(quad: Self::Quad<'_>) -> (Self::OwnedTriple, Option<Self::Term>) {
+        let graph = match quad.graph_name {
+            GraphName::NamedNode(iri) => Some(Term::NamedNode(iri)),
+            GraphName::BlankNode(bnode) => Some(Term::BlankNode(bnode)),
+            GraphName::DefaultGraph => None,
+        };
+        (
+            Triple::new(quad.subject, quad.predicate, quad.object),
+            graph,
+        )
+    }
+}
@@ -0,0 +1,326 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::Inner;
+use crate::deserialize::ToTerm as _;
+#[cfg(feature = "value")]
+use crate::error::ValueError;
+use crate::error::{DeserializeError, TermLocation};
+use crate::from_rdf::{LiteralKind as TermLiteralKind, Term};
+use crate::lookup::LookupType;
+use crate::proto::{RdfIri, RdfLiteral, RdfTriple, rdf_literal::LiteralKind};
+#[cfg(feature = "value")]
+use crate::value::Value;
+
+use super::{ToOwnedRdf, ToRdf};
+
+/// Decodes IRIs and datatypes into shared copies of the lookup table entries they are
+/// made of, instead of joining them into new strings.
+///
+/// Each table entry is copied into an [`Arc<str>`] once, when a term first refers to it,
+/// and every later term holds another reference to it. Terms own their data, so cloning
+/// one to keep it only bumps reference counts. Blank nodes and literal forms are the
+/// strings of the row. [`SharedTerm::to_term`] builds a [`Term`] with full strings.
+pub struct SharedRdf;
+
+/// Terms compare and hash IRIs by their full IRI, no matter how it is split into prefix
+/// and name.
+#[derive(Debug, Clone)]
+pub enum SharedTerm {
+    Iri {
+        prefix: Arc<str>,
+        name: Arc<str>,
+    },
+    BlankNode(String),
+    Literal {
+        lex: String,
+        kind: SharedLiteralKind,
+    },
+    Triple(Box<[SharedTerm; 3]>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SharedLiteralKind {
+    Simple,
+    Language(String),
+    Datatype(Arc<str>),
+}
+
+impl SharedTerm {
+    /// The prefix and name of an IRI, concatenated they are the full IRI.
+    pub fn iri(&self) -> Option<(&str, &str)> {
+        match self {
+            SharedTerm::Iri { prefix, name } => Some((prefix, name)),
+            _ => None,
+        }
+    }
+
+    /// Whether this is the IRI `iri`, without building it.
+    pub fn is_iri(&self, iri: &str) -> bool {
+        self.iri()
+            .is_some_and(|(prefix, name)| iri.strip_prefix(prefix).is_some_and(|rest| rest == name))
+    }
+
+    /// The typed value of a literal, `None` for other terms and for datatypes without
+    /// a [`Value`].
+    #[cfg(feature = "value")]
+    pub fn value(&self) -> Result<Option<Value>, ValueError> {
+        match self {
+            SharedTerm::Literal {
+                lex,
+                kind: SharedLiteralKind::Datatype(datatype),
+            } => Value::parse(lex, datatype),
+            _ => Ok(None),
+        }
+    }
+
+    /// Copies the term into a [`Term`], which can be passed to the serializer again.
+    pub fn to_term(&self) -> Term {
+        match self {
+            SharedTerm::Iri { prefix, name } => Term::Iri(format!("{}{}", prefix, name)),
+            SharedTerm::BlankNode(id) => Term::BlankNode(id.clone()),
+            SharedTerm::Literal { lex, kind } => Term::Literal(
+                lex.clone(),
+                match kind {
+                    SharedLiteralKind::Simple => TermLiteralKind::Simple,
+                    SharedLiteralKind::Language(tag) => TermLiteralKind::Language(tag.clone()),
+                    SharedLiteralKind::Datatype(iri) => TermLiteralKind::Datatype(iri.to_string()),
+                },
+            ),
+            SharedTerm::Triple(triple) => {
+                Term::Triple(Box::new(std::array::from_fn(|i| triple[i].to_term())))
+            }
+        }
+    }
+}
+
+/// Writes the term in N-Triples syntax.
+impl fmt::Display for SharedTerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SharedTerm::Iri { prefix, name } => write!(f, "<{}{}>", prefix, name),
+            SharedTerm::BlankNode(id) => write!(f, "_:B{}", id),
+            SharedTerm::Literal { lex, kind } => {
+                f.write_str("\"")?;
+                for c in lex.chars() {
+                    match c {
+                        '\\' => f.write_str("\\\\")?,
+                        '"' => f.write_str("\\\"")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        c => write!(f, "{}", c)?,
+                    }
+                }
+                f.write_str("\"")?;
+                match kind {
+                    SharedLiteralKind::Simple => Ok(()),
+                    SharedLiteralKind::Language(tag) => write!(f, "@{}", tag),
+                    SharedLiteralKind::Datatype(iri) => write!(f, "^^<{}>", iri),
+                }
+            }
+            SharedTerm::Triple(triple) => {
+                write!(f, "<< {} {} {} >>", triple[0], triple[1], triple[2])
+            }
+        }
+    }
+}
+
+/// Whether the IRIs split into `(prefix, name)` are the same, without joining them.
+fn same_iri(a: (&str, &str), b: (&str, &str)) -> bool {
+    let ((short_prefix, short_name), (long_prefix, long_name)) = if a.0.len() <= b.0.len() {
+        (a, b)
+    } else {
+        (b, a)
+    };
+    // The longer prefix is the shorter one followed by the start of its name
+    long_prefix
+        .strip_prefix(short_prefix)
+        .and_then(|middle| short_name.strip_prefix(middle))
+        .is_some_and(|rest| rest == long_name)
+}
+
+impl PartialEq for SharedTerm {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                SharedTerm::Iri { prefix, name },
+                SharedTerm::Iri {
+                    prefix: other_prefix,
+                    name: other_name,
+                },
+            ) => same_iri((prefix, name), (other_prefix, other_name)),
+            (SharedTerm::BlankNode(id), SharedTerm::BlankNode(other)) => id == other,
+            (
+                SharedTerm::Literal { lex, kind },
+                SharedTerm::Literal {
+                    lex: other_lex,
+                    kind: other_kind,
+                },
+            ) => lex == other_lex && kind == other_kind,
+            (SharedTerm::Triple(triple), SharedTerm::Triple(other)) => triple == other,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for SharedTerm {}
+
+impl Hash for SharedTerm {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            SharedTerm::Iri { prefix, name } => {
+                // Hashed in chunks of the full IRI, so the split does not change the hash
+                let mut chunk = [0u8; 32];
+                let mut filled = 0;
+                for mut part in [prefix.as_bytes(), name.as_bytes()] {
+                    while !part.is_empty() {
+                        let len = part.len().min(chunk.len() - filled);
+                        chunk[filled..filled + len].copy_from_slice(&part[..len]);
+                        filled += len;
+                        part = &part[len..];
+                        if filled == chunk.len() {
+                            state.write(&chunk);
+                            filled = 0;
+                        }
+                    }
+                }
+                state.write(&chunk[..filled]);
+                state.write_u8(0xff);
+            }
+            SharedTerm::BlankNode(id) => id.hash(state),
+            SharedTerm::Literal { lex, kind } => {
+                lex.hash(state);
+                kind.hash(state);
+            }
+            SharedTerm::Triple(triple) => triple.hash(state),
+        }
+    }
+}
+
+fn last_terms(d: &Inner<SharedRdf>) -> Result<[&SharedTerm; 3], DeserializeError> {
+    Ok([
+        d.last_subject
+            .as_ref()
+            .ok_or(DeserializeError::MissingTerm(TermLocation::Subject))?,
+        d.last_predicate
+            .as_ref()
+            .ok_or(DeserializeError::MissingTerm(TermLocation::Predicate))?,
+        d.last_object
+            .as_ref()
+            .ok_or(DeserializeError::MissingTerm(TermLocation::Object))?,
+    ])
+}
+
+impl ToRdf for SharedRdf {
+    type Term = SharedTerm;
+
+    type Triple<'b> = [&'b SharedTerm; 3];
+
+    type Quad<'b> = ([&'b SharedTerm; 3], Option<&'b SharedTerm>);
+
+    type State = ();
+
+    fn default_term() -> Self::Term {
+        SharedTerm::BlankNode(String::new())
+    }
+
+    #[inline]
+    fn iri(iri: RdfIri, deserializer: &mut Inner<Self>) -> Result<Self::Term, DeserializeError> {
+        let prefix = deserializer
+            .prefix_table
+            .get_shared(iri.prefix_id, LookupType::Stay)?
+            .clone();
+        let name = deserializer
+            .name_table
+            .get_shared(iri.name_id, LookupType::Inc)?
+            .clone();
+        Ok(SharedTerm::Iri { prefix, name })
+    }
+
+    #[inline]
+    fn bnode(key: String, _: &mut Inner<Self>) -> Result<Self::Term, DeserializeError> {
+        Ok(SharedTerm::BlankNode(key))
+    }
+
+    #[inline]
+    fn literal(
+        literal: RdfLiteral,
+        deserializer: &mut Inner<Self>,
+    ) -> Result<Self::Term, DeserializeError> {
+        let kind = match literal.literal_kind {
+            Some(LiteralKind::Langtag(tag)) => SharedLiteralKind::Language(tag),
+            Some(LiteralKind::Datatype(tag)) => SharedLiteralKind::Datatype(
+                deserializer
+                    .datatype_table
+                    .get_shared(tag, LookupType::Invalid)?
+                    .clone(),
+            ),
+            None => SharedLiteralKind::Simple,
+        };
+        Ok(SharedTerm::Literal {
+            lex: literal.lex,
+            kind,
+        })
+    }
+
+    fn term_triple(
+        triple: RdfTriple,
+        deserializer: &mut Inner<Self>,
+    ) -> Result<Self::Term, DeserializeError> {
+        let RdfTriple {
+            subject,
+            predicate,
+            object,
+        } = triple;
+        let s = if let Some(s) = subject {
+            deserializer.to_term(s)?
+        } else {
+            return Err(DeserializeError::MissingTermTermTriple(
+                TermLocation::Subject,
+            ));
+        };
+
+        let p = if let Some(p) = predicate {
+            deserializer.to_term(p)?
+        } else {
+            return Err(DeserializeError::MissingTermTermTriple(
+                TermLocation::Predicate,
+            ));
+        };
+
+        let o = if let Some(o) = object {
+            deserializer.to_term(o)?
+        } else {
+            return Err(DeserializeError::MissingTermTermTriple(
+                TermLocation::Object,
+            ));
+        };
+
+        Ok(SharedTerm::Triple(Box::new([s, p, o])))
+    }
+
+    #[inline]
+    fn triple<'b>(d: &'b mut Inner<Self>) -> Result<Self::Triple<'b>, DeserializeError> {
+        last_terms(d)
+    }
+
+    #[inline]
+    fn quad<'b>(d: &'b mut Inner<Self>) -> Result<Self::Quad<'b>, DeserializeError> {
+        Ok((last_terms(d)?, d.last_graph.as_ref()))
+    }
+}
+
+impl ToOwnedRdf for SharedRdf {
+    type OwnedTriple = [SharedTerm; 3];
+
+    fn owned_triple(triple: Self::Triple<'_>) -> Self::OwnedTriple {
+        triple.map(SharedTerm::clone)
+    }
+
+    fn owned_quad(quad: Self::Quad<'_>) -> (Self::OwnedTriple, Option<Self::Term>) {
+        (quad.0.map(SharedTerm::clone), quad.1.cloned())
+    }
+}
@@ -1,4 +1,7 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    sync::{Arc, LazyLock},
+};
 
 use sophia_api::term::{BnodeId, IriRef, LanguageTag};
 use sophia_term::ArcTerm;
@@ -10,12 +13,85 @@ use crate::{
     lookup::LookupType,
     proto::{RdfIri, RdfLiteral, RdfTriple, rdf_literal::LiteralKind},
 };
+#[cfg(feature = "value")]
+use crate::{error::ValueError, value::Value};
+
+use super::{ToOwnedRdf, ToRdf};
+
+const DEFAULT_DATA_TYPE: &str = "http://www.w3.org/2001/XMLSchema#string";
+
+static XSD_STRING: LazyLock<IriRef<Arc<str>>> =
+    LazyLock::new(|| IriRef::new_unchecked(Arc::from(DEFAULT_DATA_TYPE)));
 
-use super::ToRdf;
+/// Language tags are interned until a stream used this many distinct ones
+const MAX_LANGUAGE_TAGS: usize = 1024;
 
-const DEFAULT_DATA_TYPE: &'static str = "http://www.w3.org/2001/XMLSchema#string";
+/// Terms shared between the statements of a stream.
+#[derive(Default, Clone)]
+pub struct SophiaState {
+    bnodes: HashMap<String, ArcTerm>,
+    // Validated datatype per datatype table id, with the generation of the entry
+    datatypes: Vec<Option<(u32, IriRef<Arc<str>>)>>,
+    languages: HashMap<String, LanguageTag<Arc<str>>>,
+}
+
+impl SophiaState {
+    fn language(&mut self, tag: String) -> Result<LanguageTag<Arc<str>>, DeserializeError> {
+        if self.languages.len() >= MAX_LANGUAGE_TAGS && !self.languages.contains_key(&tag) {
+            self.languages.clear();
+        }
+        Ok(match self.languages.entry(tag) {
+            Entry::Occupied(entry) => entry.get().clone(),
+            Entry::Vacant(entry) => {
+                let lang = LanguageTag::new(Arc::from(entry.key().as_str()))?;
+                entry.insert(lang).clone()
+            }
+        })
+    }
+}
+
+/// Only validates a datatype again once its table entry was overwritten.
+fn datatype(
+    deserializer: &mut Inner<SophiaRdf>,
+    tag: u32,
+) -> Result<IriRef<Arc<str>>, DeserializeError> {
+    let id = deserializer
+        .datatype_table
+        .resolve(tag, LookupType::Invalid)?;
+    let (iri, generation) = deserializer.datatype_table.entry(id)?;
+
+    let datatypes = &mut deserializer.state.datatypes;
+    if let Some((_, datatype)) = datatypes
+        .get(id)
+        .and_then(Option::as_ref)
+        .filter(|(cached, _)| *cached == generation)
+    {
+        return Ok(datatype.clone());
+    }
+    let datatype = IriRef::new(Arc::from(iri.as_ref()))?;
+    if datatypes.len() <= id {
+        datatypes.resize(id + 1, None);
+    }
+    datatypes[id] = Some((generation, datatype.clone()));
+    Ok(datatype)
+}
 
 pub struct SophiaRdf;
+
+#[cfg(feature = "value")]
+impl SophiaRdf {
+    /// The typed value of a literal term, `None` for other terms and for datatypes without
+    /// a [`Value`].
+    pub fn value(term: &ArcTerm) -> Result<Option<Value>, ValueError> {
+        match term {
+            ArcTerm::Literal(sophia_term::GenericLiteral::Typed(lex, datatype)) => {
+                Value::parse(lex, datatype.as_str())
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
 impl ToRdf for SophiaRdf {
     type Term = ArcTerm;
 
@@ -23,26 +99,24 @@ impl ToRdf for SophiaRdf {
 
     type Quad<'b> = ([ArcTerm; 3], Option<ArcTerm>);
 
-    type State = HashMap<String, ArcTerm>;
+    type State = SophiaState;
 
     fn default_term() -> Self::Term {
         ArcTerm::Iri(IriRef::new_unchecked(Arc::from("")))
     }
 
     fn iri(iri: RdfIri, deserializer: &mut Inner<Self>) -> Result<Self::Term, DeserializeError> {
-        let iri = format!(
-            "{}{}",
-            deserializer
-                .prefix_table
-                .get(iri.prefix_id, LookupType::Stay)?,
-            deserializer.name_table.get(iri.name_id, LookupType::Inc)?
-        );
-        Ok(ArcTerm::Iri(IriRef::new_unchecked(Arc::from(iri))))
+        // Repeated IRIs share the allocation of the first term built for them
+        deserializer.cached_iri(iri, |prefix, name| {
+            let iri = format!("{}{}", prefix, name);
+            Ok(ArcTerm::Iri(IriRef::new_unchecked(Arc::from(iri))))
+        })
     }
 
     fn bnode(key: String, deserializer: &mut Inner<Self>) -> Result<Self::Term, DeserializeError> {
         Ok(deserializer
             .state
+            .bnodes
             .entry(key)
             .or_insert_with_key(|key| {
                 ArcTerm::BlankNode(BnodeId::new_unchecked(Arc::from(format!("b{}", key))))
@@ -57,23 +131,14 @@ impl ToRdf for SophiaRdf {
         let lex = Arc::from(literal.lex);
         Ok(match literal.literal_kind {
             Some(LiteralKind::Langtag(tag)) => {
-                let lang = LanguageTag::new(Arc::from(tag))?;
+                let lang = deserializer.state.language(tag)?;
                 ArcTerm::Literal(sophia_term::GenericLiteral::LanguageString(lex, lang, None))
             }
             Some(LiteralKind::Datatype(tag)) => {
-                let datatype = IriRef::new(Arc::from(
-                    deserializer
-                        .datatype_table
-                        .get(tag, LookupType::Invalid)?
-                        .to_string(),
-                ))?;
-
-                ArcTerm::Literal(sophia_term::GenericLiteral::Typed(lex, datatype))
-            }
-            None => {
-                let datatype = IriRef::new(Arc::from(DEFAULT_DATA_TYPE))?;
+                let datatype = datatype(deserializer, tag)?;
                 ArcTerm::Literal(sophia_term::GenericLiteral::Typed(lex, datatype))
             }
+            None => ArcTerm::Literal(sophia_term::GenericLiteral::Typed(lex, XSD_STRING.clone())),
         })
     }
 
@@ -144,3 +209,15 @@ impl ToRdf for SophiaRdf {
         ))
     }
 }
+
+impl ToOwnedRdf for SophiaRdf {
+    type OwnedTriple = [ArcTerm; 3];
+
+    fn owned_triple(triple: Self::Triple<'_>) -> Self::OwnedTriple {
+        triple
+    }
+
+    fn owned_quad(quad: Self::Quad<'_>) -> (Self::OwnedTriple, Option<Self::Term>) {
+        quad
+    }
+}
@@ -3,8 +3,10 @@ use crate::deserialize::{RdfHandler, ToTerm as _};
 use crate::error::{DeserializeError, TermLocation};
 use crate::lookup::LookupType;
 use crate::proto::{RdfIri, RdfLiteral, RdfTriple, rdf_literal::LiteralKind};
+#[cfg(feature = "value")]
+use crate::{error::ValueError, value::Value};
 
-use super::ToRdf;
+use super::{ToOwnedRdf, ToRdf};
 
 /// Escape quotes and control characters in literal values for N-Triples format
 fn escape_literal(s: &str) -> String {
@@ -15,8 +17,49 @@ fn escape_literal(s: &str) -> String {
         .replace('\t', "\\t")
 }
 
+/// Reverts [`escape_literal`]
+#[cfg(feature = "value")]
+fn unescape_literal(s: &str) -> std::borrow::Cow<'_, str> {
+    if !s.contains('\\') {
+        return s.into();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+    out.into()
+}
+
 pub struct StringRdf;
 
+#[cfg(feature = "value")]
+impl StringRdf {
+    /// The typed value of a literal term, `None` for other terms and for datatypes without
+    /// a [`Value`].
+    pub fn value(term: &str) -> Result<Option<Value>, ValueError> {
+        // Datatype IRIs can not contain quotes, so the last `"^^<` ends the lexical form
+        match term
+            .strip_prefix('"')
+            .and_then(|term| term.strip_suffix('>'))
+            .and_then(|term| term.rsplit_once("\"^^<"))
+        {
+            Some((lex, datatype)) => Value::parse(&unescape_literal(lex), datatype),
+            None => Ok(None),
+        }
+    }
+}
+
 impl ToRdf for StringRdf {
     type Term = String;
 
@@ -31,13 +74,17 @@ impl ToRdf for StringRdf {
 
     #[inline]
     fn iri(iri: RdfIri, deserializer: &mut Inner<Self>) -> Result<Self::Term, DeserializeError> {
-        Ok(format!(
-            "<{}{}>",
-            deserializer
-                .prefix_table
-                .get(iri.prefix_id, LookupType::Stay)?,
-            deserializer.name_table.get(iri.name_id, LookupType::Inc)?
-        ))
+        // Not cached, a cache hit would clone a String as long as the one built here
+        let prefix = deserializer
+            .prefix_table
+            .get(iri.prefix_id, LookupType::Stay)?;
+        let name = deserializer.name_table.get(iri.name_id, LookupType::Inc)?;
+        let mut term = String::with_capacity(prefix.len() + name.len() + 2);
+        term.push('<');
+        term.push_str(prefix);
+        term.push_str(name);
+        term.push('>');
+        Ok(term)
     }
 
     #[inline]
@@ -106,15 +153,15 @@ impl ToRdf for StringRdf {
     #[inline]
     fn triple<'a>(deserializer: &'a mut Inner<Self>) -> Result<Self::Triple<'a>, DeserializeError> {
         Ok((
-            &deserializer
+            deserializer
                 .last_subject
                 .as_ref()
                 .ok_or(DeserializeError::MissingTerm(TermLocation::Subject))?,
-            &deserializer
+            deserializer
                 .last_predicate
                 .as_ref()
                 .ok_or(DeserializeError::MissingTerm(TermLocation::Predicate))?,
-            &deserializer
+            deserializer
                 .last_object
                 .as_ref()
                 .ok_or(DeserializeError::MissingTerm(TermLocation::Object))?,
@@ -124,24 +171,39 @@ impl ToRdf for StringRdf {
     #[inline]
     fn quad<'a>(deserializer: &'a mut Inner<Self>) -> Result<Self::Quad<'a>, DeserializeError> {
         Ok((
-            &deserializer
+            deserializer
                 .last_subject
                 .as_ref()
                 .ok_or(DeserializeError::MissingTerm(TermLocation::Subject))?,
-            &deserializer
+            deserializer
                 .last_predicate
                 .as_ref()
                 .ok_or(DeserializeError::MissingTerm(TermLocation::Predicate))?,
-            &deserializer
+            deserializer
                 .last_object
                 .as_ref()
                 .ok_or(DeserializeError::MissingTerm(TermLocation::Object))?,
-            deserializer.last_graph.as_ref().map(|x| x.as_str()),
+            deserializer.last_graph.as_deref(),
         ))
     }
 }
 
-impl<'a> RdfHandler<StringRdf> for &mut Vec<(String, String, String, Option<String>)> {
+impl ToOwnedRdf for StringRdf {
+    type OwnedTriple = (String, String, String);
+
+    fn owned_triple((s, p, o): Self::Triple<'_>) -> Self::OwnedTriple {
+        (s.to_string(), p.to_string(), o.to_string())
+    }
+
+    fn owned_quad((s, p, o, g): Self::Quad<'_>) -> (Self::OwnedTriple, Option<Self::Term>) {
+        (
+            (s.to_string(), p.to_string(), o.to_string()),
+            g.map(String::from),
+        )
+    }
+}
+
+impl RdfHandler<StringRdf> for &mut Vec<(String, String, String, Option<String>)> {
     fn handle_triple<'b>(&mut self, (s, p, o): <StringRdf as ToRdf>::Triple<'b>) {
         self.push((s.to_string(), p.to_string(), o.to_string(), None));
     }
@@ -0,0 +1,409 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use num_bigint::BigInt;
+
+use crate::error::ValueError;
+
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// The datatypes literals can be parsed into a [`Value`] for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum XsdType {
+    Integer,
+    Decimal,
+    Double,
+    Boolean,
+    DateTime,
+}
+
+impl XsdType {
+    pub fn from_iri(iri: &str) -> Option<Self> {
+        match iri.strip_prefix(XSD)? {
+            "integer" => Some(XsdType::Integer),
+            "decimal" => Some(XsdType::Decimal),
+            "double" => Some(XsdType::Double),
+            "boolean" => Some(XsdType::Boolean),
+            "dateTime" => Some(XsdType::DateTime),
+            _ => None,
+        }
+    }
+
+    pub fn iri(self) -> &'static str {
+        match self {
+            XsdType::Integer => "http://www.w3.org/2001/XMLSchema#integer",
+            XsdType::Decimal => "http://www.w3.org/2001/XMLSchema#decimal",
+            XsdType::Double => "http://www.w3.org/2001/XMLSchema#double",
+            XsdType::Boolean => "http://www.w3.org/2001/XMLSchema#boolean",
+            XsdType::DateTime => "http://www.w3.org/2001/XMLSchema#dateTime",
+        }
+    }
+}
+
+impl fmt::Display for XsdType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "xsd:{}", &self.iri()[XSD.len()..])
+    }
+}
+
+/// The value of a literal, parsed from its lexical form.
+///
+/// Decode with [`SharedRdf`](crate::to_rdf::SharedRdf) and call
+/// [`SharedTerm::value`](crate::to_rdf::SharedTerm::value), pass the terms of the other
+/// backends to [`StringRdf::value`](crate::to_rdf::StringRdf::value) or `SophiaRdf::value`,
+/// or parse any lexical form with [`Value::parse`]. A
+/// [`TryRdfHandler`](crate::deserialize::TryRdfHandler) can return the [`ValueError`] to stop
+/// decoding at the first malformed literal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// An `xsd:integer` that fits into an `i64`
+    Integer(i64),
+    /// Any other `xsd:integer`
+    BigInteger(BigInt),
+    Decimal(Decimal),
+    Double(f64),
+    Boolean(bool),
+    DateTime(DateTime),
+}
+
+impl Value {
+    /// Parses `lex` if `datatype` is one of the [`XsdType`]s, `None` for other datatypes.
+    pub fn parse(lex: &str, datatype: &str) -> Result<Option<Value>, ValueError> {
+        match XsdType::from_iri(datatype) {
+            Some(ty) => Value::parse_as(lex, ty).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn parse_as(lex: &str, ty: XsdType) -> Result<Value, ValueError> {
+        let value = match ty {
+            XsdType::Integer => parse_integer(lex),
+            XsdType::Decimal => parse_decimal(lex).map(Value::Decimal),
+            XsdType::Double => parse_double(lex).map(Value::Double),
+            XsdType::Boolean => match lex {
+                "true" | "1" => Some(Value::Boolean(true)),
+                "false" | "0" => Some(Value::Boolean(false)),
+                _ => None,
+            },
+            XsdType::DateTime => parse_date_time(lex).map(Value::DateTime),
+        };
+        value.ok_or_else(|| ValueError::Malformed {
+            datatype: ty,
+            lex: lex.to_string(),
+        })
+    }
+
+    pub fn datatype(&self) -> XsdType {
+        match self {
+            Value::Integer(_) | Value::BigInteger(_) => XsdType::Integer,
+            Value::Decimal(_) => XsdType::Decimal,
+            Value::Double(_) => XsdType::Double,
+            Value::Boolean(_) => XsdType::Boolean,
+            Value::DateTime(_) => XsdType::DateTime,
+        }
+    }
+
+    /// The value of integers within the range of an `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Numeric values as the closest `f64`.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Integer(i) => Some(*i as f64),
+            Value::BigInteger(i) => i.to_string().parse().ok(),
+            Value::Decimal(d) => Some(d.to_f64()),
+            Value::Double(d) => Some(*d),
+            _ => None,
+        }
+    }
+}
+
+/// An `xsd:decimal`, `unscaled / 10^scale` without trailing zeros in the fraction.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Decimal {
+    unscaled: BigInt,
+    scale: u32,
+}
+
+impl Decimal {
+    pub fn unscaled(&self) -> &BigInt {
+        &self.unscaled
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.unscaled.magnitude().to_string();
+        let scale = self.scale as usize;
+        if self.unscaled.sign() == num_bigint::Sign::Minus {
+            f.write_str("-")?;
+        }
+        if scale == 0 {
+            return f.write_str(&digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (int, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{}.{}", int, fraction)
+    }
+}
+
+/// An `xsd:dateTime`, `offset` is the timezone in minutes east of UTC.
+///
+/// The year is in the proleptic Gregorian calendar with year 0 before year 1, the
+/// hour is 24 only for the end of the day `24:00:00`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DateTime {
+    pub year: i64,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub nanosecond: u32,
+    pub offset: Option<i16>,
+}
+
+impl DateTime {
+    /// The point in time, `None` without a timezone or outside the range of [`SystemTime`].
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        let offset = i128::from(self.offset?);
+        let seconds = days_from_civil(self.year, self.month, self.day) * 86_400
+            + i128::from(self.hour) * 3600
+            + i128::from(self.minute) * 60
+            + i128::from(self.second)
+            - offset * 60;
+        let nanos = Duration::from_nanos(self.nanosecond.into());
+        if seconds >= 0 {
+            UNIX_EPOCH
+                .checked_add(Duration::from_secs(u64::try_from(seconds).ok()?))?
+                .checked_add(nanos)
+        } else {
+            UNIX_EPOCH
+                .checked_sub(Duration::from_secs(u64::try_from(-seconds).ok()?))?
+                .checked_add(nanos)
+        }
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.year < 0 {
+            f.write_str("-")?;
+        }
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year.unsigned_abs(),
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second
+        )?;
+        if self.nanosecond > 0 {
+            let fraction = format!("{:09}", self.nanosecond);
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        match self.offset {
+            None => Ok(()),
+            Some(0) => f.write_str("Z"),
+            Some(offset) => {
+                let sign = if offset < 0 { '-' } else { '+' };
+                let offset = offset.unsigned_abs();
+                write!(f, "{}{:02}:{:02}", sign, offset / 60, offset % 60)
+            }
+        }
+    }
+}
+
+fn digits(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn split_sign(s: &str) -> (bool, &str) {
+    match s.as_bytes().first() {
+        Some(b'-') => (true, &s[1..]),
+        Some(b'+') => (false, &s[1..]),
+        _ => (false, s),
+    }
+}
+
+/// Digits with an optional fraction, at least one digit on either side of the point
+fn split_fraction(s: &str) -> Option<(&str, &str)> {
+    let (int, fraction) = s.split_once('.').unwrap_or((s, ""));
+    let valid = (digits(int) || int.is_empty())
+        && (digits(fraction) || fraction.is_empty())
+        && !(int.is_empty() && fraction.is_empty());
+    valid.then_some((int, fraction))
+}
+
+fn parse_integer(lex: &str) -> Option<Value> {
+    if !digits(split_sign(lex).1) {
+        return None;
+    }
+    match lex.parse() {
+        Ok(i) => Some(Value::Integer(i)),
+        Err(_) => BigInt::from_str(lex).ok().map(Value::BigInteger),
+    }
+}
+
+fn parse_decimal(lex: &str) -> Option<Decimal> {
+    let (negative, rest) = split_sign(lex);
+    let (int, fraction) = split_fraction(rest)?;
+    let fraction = fraction.trim_end_matches('0');
+    let mut unscaled = BigInt::from_str(&format!("0{}{}", int, fraction)).ok()?;
+    if negative {
+        unscaled = -unscaled;
+    }
+    Some(Decimal {
+        unscaled,
+        scale: fraction.len() as u32,
+    })
+}
+
+fn parse_double(lex: &str) -> Option<f64> {
+    match lex {
+        "INF" | "+INF" => return Some(f64::INFINITY),
+        "-INF" => return Some(f64::NEG_INFINITY),
+        "NaN" => return Some(f64::NAN),
+        _ => {}
+    }
+    let (_, rest) = split_sign(lex);
+    let (mantissa, exponent) = match rest.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (rest, None),
+    };
+    split_fraction(mantissa)?;
+    if exponent.is_some_and(|e| !digits(split_sign(e).1)) {
+        return None;
+    }
+    lex.parse().ok()
+}
+
+fn two_digits(s: &str) -> Option<u8> {
+    if s.len() == 2 && digits(s) {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year.rem_euclid(4) == 0 && (year.rem_euclid(100) != 0 || year.rem_euclid(400) == 0)
+}
+
+fn days_in_month(year: i64, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01
+fn days_from_civil(year: i64, month: u8, day: u8) -> i128 {
+    let (month, day) = (i128::from(month), i128::from(day));
+    let year = i128::from(year) - i128::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// `-?YYYY-MM-DDThh:mm:ss(.s+)?(Z|(+|-)hh:mm)?`
+fn parse_date_time(lex: &str) -> Option<DateTime> {
+    let (negative, rest) = match lex.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, lex),
+    };
+    let (year, rest) = rest.split_once('-')?;
+    if !digits(year) || year.len() < 4 || (year.len() > 4 && year.starts_with('0')) {
+        return None;
+    }
+    let year: i64 = year.parse().ok()?;
+    // Year 0 has no sign, `-0000` is not a year
+    if negative && year == 0 {
+        return None;
+    }
+    let year = if negative { -year } else { year };
+
+    let (date, time) = rest.split_once('T')?;
+    let (month, day) = date.split_once('-')?;
+    let (month, day) = (two_digits(month)?, two_digits(day)?);
+
+    let (time, offset) = if let Some(time) = time.strip_suffix('Z') {
+        (time, Some(0))
+    } else if time.len() > 6 && matches!(time.as_bytes()[time.len() - 6], b'+' | b'-') {
+        let (time, zone) = time.split_at(time.len() - 6);
+        let (hours, minutes) = zone[1..].split_once(':')?;
+        let (hours, minutes) = (two_digits(hours)?, two_digits(minutes)?);
+        let offset = i16::from(hours) * 60 + i16::from(minutes);
+        if minutes > 59 || offset > 14 * 60 {
+            return None;
+        }
+        (
+            time,
+            Some(if zone.starts_with('-') {
+                -offset
+            } else {
+                offset
+            }),
+        )
+    } else {
+        (time, None)
+    };
+
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) if digits(fraction) => (time, Some(fraction)),
+        Some(_) => return None,
+        None => (time, None),
+    };
+    let mut parts = time.split(':');
+    let hour = two_digits(parts.next()?)?;
+    let minute = two_digits(parts.next()?)?;
+    let second = two_digits(parts.next()?)?;
+    if parts.next().is_some() {
+        return None;
+    }
+    // Digits beyond nanoseconds are dropped
+    let nanosecond = fraction.map_or(0, |fraction| {
+        let fraction = &fraction[..fraction.len().min(9)];
+        fraction.parse::<u32>().unwrap_or(0) * 10u32.pow(9 - fraction.len() as u32)
+    });
+
+    let end_of_day = hour == 24
+        && minute == 0
+        && second == 0
+        && fraction.is_none_or(|fraction| fraction.bytes().all(|b| b == b'0'));
+    let valid = (1..=12).contains(&month)
+        && (1..=days_in_month(year, month)).contains(&day)
+        && (hour < 24 || end_of_day)
+        && minute < 60
+        && second < 60;
+    valid.then_some(DateTime {
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        nanosecond,
+        offset,
+    })
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "tokio")]
+
+use prost::Message as _;
+
+use jelly::{
+    async_io::{AsyncFrameReader, AsyncFrameWriter},
+    error::FrameReadError,
+    proto::RdfStreamFrame,
+};
+
+mod common;
+use common::frames;
+
+async fn read_all<R>(
+    mut reader: AsyncFrameReader<R>,
+) -> (Vec<RdfStreamFrame>, Option<FrameReadError>)
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    let mut read = Vec::new();
+    while let Some(frame) = reader.next_frame().await {
+        match frame {
+            Ok(frame) => read.push(frame),
+            Err(e) => return (read, Some(e)),
+        }
+    }
+    (read, None)
+}
+
+#[tokio::test]
+async fn round_trip_over_duplex() {
+    let frames = frames(100);
+    assert!(frames.len() > 1);
+
+    // A small pipe, so frames are written and read in several parts
+    let (client, server) = tokio::io::duplex(64);
+
+    let written = frames.clone();
+    let producer = tokio::spawn(async move {
+        let mut writer = AsyncFrameWriter::new(client);
+        writer.set_flush_after_frame(true);
+        writer.write_frames(written).await.unwrap();
+        writer.shutdown().await.unwrap();
+    });
+
+    let (read, error) = read_all(AsyncFrameReader::new(server)).await;
+    producer.await.unwrap();
+    assert!(error.is_none());
+    assert_eq!(read, frames);
+}
+
+#[tokio::test]
+async fn reports_truncated_frames() {
+    let frames = frames(20);
+    let mut writer = AsyncFrameWriter::new(Vec::new());
+    writer.write_frames(frames.clone()).await.unwrap();
+    let mut bytes = writer.into_inner().await.unwrap();
+
+    let first = frames[0].encoded_len();
+    let second_start = (first + prost::length_delimiter_len(first)) as u64;
+    bytes.truncate(second_start as usize + 10);
+
+    let (read, error) = read_all(AsyncFrameReader::new(&bytes[..])).await;
+    assert_eq!(read, frames[..1]);
+    assert!(matches!(
+        error,
+        Some(FrameReadError::TruncatedFrame { offset, .. }) if offset == second_start
+    ));
+
+    let (read, error) = read_all(AsyncFrameReader::new(&[0xff; 12][..])).await;
+    assert!(read.is_empty());
+    assert!(matches!(
+        error,
+        Some(FrameReadError::InvalidLength { offset: 0 })
+    ));
+}
@@ -0,0 +1,19 @@
+use jelly::{
+    from_rdf::{LiteralKind, Term},
+    proto::{PhysicalStreamType, RdfStreamFrame},
+    serialize::{FramingPolicy, Serializer},
+};
+
+/// `count` triples with different subjects, in frames of at most 8 rows
+pub fn frames(count: usize) -> Vec<RdfStreamFrame> {
+    let mut ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Triples))
+        .expect("valid options");
+    ser.set_framing_policy(FramingPolicy::Rows(8));
+    for i in 0..count {
+        let s = Term::Iri(format!("http://example.org/s{}", i));
+        let p = Term::Iri("http://example.org/p".to_string());
+        let o = Term::Literal(i.to_string(), LiteralKind::Simple);
+        ser.triple(&s, &p, &o).unwrap();
+    }
+    ser.finish()
+}
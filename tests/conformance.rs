@@ -1,3 +1,6 @@
+// Generated from the manifest of the proto submodule, see build.rs
+#![cfg(conformance)]
+
 use std::{
     collections::HashMap,
     fs::File,
@@ -11,7 +14,11 @@ use jelly::{
     to_rdf::{StringRdf, ToRdf},
 };
 
-use jelly::{deserialize::StateHandler, to_rdf::SophiaRdf};
+use jelly::{
+    deserialize::TryRdfHandler,
+    logical::OwnedQuad,
+    to_rdf::{SophiaRdf, ToOwnedRdf},
+};
 
 use sophia_api::{
     prelude::MutableDataset,
@@ -100,6 +107,87 @@ impl DsCmp {
     }
 }
 
+/// Collects the statements of a frame as sophia quads, failed inserts abort the frame
+struct Collect<B: ToOwnedRdf> {
+    quads: Vec<Q>,
+    convert: fn(OwnedQuad<B>) -> OwnedQuad<SophiaRdf>,
+}
+
+impl<B: ToOwnedRdf> TryRdfHandler<B> for Collect<B> {
+    type Error = <Vec<Q> as MutableDataset>::MutationError;
+
+    fn try_handle_triple<'b>(&mut self, triple: B::Triple<'b>) -> Result<(), Self::Error> {
+        let quad = (self.convert)((B::owned_triple(triple), None));
+        self.quads.insert_quad(quad).map(|_| ())
+    }
+
+    fn try_handle_quad<'b>(&mut self, quad: B::Quad<'b>) -> Result<(), Self::Error> {
+        let quad = (self.convert)(B::owned_quad(quad));
+        self.quads.insert_quad(quad).map(|_| ())
+    }
+}
+
+#[cfg(feature = "oxrdf")]
+mod oxrdf_terms {
+    use std::sync::Arc;
+
+    use jelly::{logical::OwnedQuad, to_rdf::OxRdf};
+    use oxrdf::Term;
+    use sophia_api::term::{BnodeId, IriRef, LanguageTag};
+    use sophia_term::{ArcTerm, GenericLiteral};
+
+    const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+
+    fn iri(iri: String) -> ArcTerm {
+        ArcTerm::Iri(IriRef::new_unchecked(Arc::from(iri)))
+    }
+
+    fn term(term: Term) -> ArcTerm {
+        match term {
+            Term::NamedNode(node) => iri(node.into_string()),
+            Term::BlankNode(bnode) => {
+                ArcTerm::BlankNode(BnodeId::new_unchecked(Arc::from(bnode.into_string())))
+            }
+            Term::Literal(literal) => {
+                let (lex, datatype, language) = literal.destruct();
+                let lex = Arc::from(lex);
+                match language {
+                    Some(language) => ArcTerm::Literal(GenericLiteral::LanguageString(
+                        lex,
+                        LanguageTag::new_unchecked(Arc::from(language)),
+                        None,
+                    )),
+                    None => {
+                        let datatype =
+                            datatype.map_or_else(|| XSD_STRING.to_string(), |d| d.into_string());
+                        ArcTerm::Literal(GenericLiteral::Typed(
+                            lex,
+                            IriRef::new_unchecked(Arc::from(datatype)),
+                        ))
+                    }
+                }
+            }
+            Term::Triple(triple) => ArcTerm::Triple(Arc::from([
+                term(triple.subject.into()),
+                iri(triple.predicate.into_string()),
+                term(triple.object),
+            ])),
+        }
+    }
+
+    /// Converts a decoded oxrdf quad into sophia terms to compare it with the fixtures
+    pub fn quad((triple, graph): OwnedQuad<OxRdf>) -> ([ArcTerm; 3], Option<ArcTerm>) {
+        (
+            [
+                term(triple.subject.into()),
+                iri(triple.predicate.into_string()),
+                term(triple.object),
+            ],
+            graph.map(term),
+        )
+    }
+}
+
 const BASE: &'static str = "https://w3id.org/jelly/dev/tests";
 
 fn read_manifested_file(input: &str) -> Vec<u8> {
@@ -126,17 +214,31 @@ fn to_rdf12_triple_terms(content: Vec<u8>) -> Vec<u8> {
 
 #[cfg(test)]
 fn test_positive(input: &str, result: &[&str]) {
+    check_positive::<SophiaRdf>(input, result, |quad| quad);
+}
+
+#[cfg(feature = "oxrdf")]
+fn test_positive_oxrdf(input: &str, result: &[&str]) {
+    check_positive::<jelly::to_rdf::OxRdf>(input, result, oxrdf_terms::quad);
+}
+
+/// Decodes the stream with the backend `B`, every frame has to match its result file.
+fn check_positive<B: ToOwnedRdf>(
+    input: &str,
+    result: &[&str],
+    convert: fn(OwnedQuad<B>) -> OwnedQuad<SophiaRdf>,
+) {
     use log::{debug, trace};
 
     let content = read_manifested_file(input);
-    let frames = FrameReader::new(Cursor::new(content));
+    let frames = FrameReader::auto(Cursor::new(content)).expect("readable file");
 
     trace!("test case with {:?} frame results", result);
     let mut result_iter = result.into_iter();
-    let mut errored = false;
-    let mut des = Deserializer::<SophiaRdf>::new();
+    let mut des = Deserializer::<B>::new();
 
     for frame in frames {
+        let frame = frame.unwrap_or_else(|e| panic!("positive tests should not error: {e:?}"));
         let this_result = result_iter
             .next()
             .expect("equal amount of frames to results");
@@ -144,29 +246,18 @@ fn test_positive(input: &str, result: &[&str]) {
 
         let file = to_rdf12_triple_terms(read_manifested_file(this_result));
 
-        let graph_handler = StateHandler::new(
-            Vec::new(),
-            |triple: <SophiaRdf as ToRdf>::Triple<'_>, state: &mut Vec<Q>| {
-                state
-                    .insert_quad((triple, None))
-                    .expect("triple inserted succesfully");
-            },
-            |quad: <SophiaRdf as ToRdf>::Quad<'_>, state: &mut Vec<Q>| {
-                state.insert_quad(quad).expect("quad inserted succesfully");
-            },
-        );
+        let graph_handler = Collect {
+            quads: Vec::new(),
+            convert,
+        };
 
         let expected_quads: Vec<Q> = sophia_turtle::parser::gnq::parse_bufread(Cursor::new(file))
             .collect_quads()
             .expect("valid nquads");
 
         let graph = match des.handle_frame(frame, graph_handler) {
-            Err(e) => {
-                println!("error {:?}", e);
-                errored = true;
-                break;
-            }
-            Ok(h) => h.state,
+            Err(e) => panic!("positive tests should not error: {e:?}"),
+            Ok(h) => h.quads,
         };
 
         let mut state = DsCmp::new();
@@ -174,13 +265,11 @@ fn test_positive(input: &str, result: &[&str]) {
         let ds_eq = state.eq_ds(&expected_quads, &graph);
         assert!(ds_eq, "same same");
     }
-
-    assert!(!errored, "positives tests should result in not error");
 }
 
 fn test_negative(input: &str) {
     let content = read_manifested_file(input);
-    let frames = FrameReader::new(Cursor::new(content));
+    let frames = FrameReader::auto(Cursor::new(content)).expect("readable file");
 
     let mut errored = false;
     let mut des = Deserializer::<StringRdf>::new();
@@ -191,6 +280,10 @@ fn test_negative(input: &str) {
     );
 
     for frame in frames {
+        let Ok(frame) = frame else {
+            errored = true;
+            break;
+        };
         if let Err(_) = des.handle_frame(frame, &mut h) {
             errored = true;
             break;
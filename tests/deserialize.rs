@@ -0,0 +1,676 @@
+use std::collections::HashMap;
+
+use jelly::{
+    deserialize::{Deserializer, PrefixCollector, RdfHandler, TryRdfHandler},
+    error::{ConfigError, DeserializeError, TermKind, TermLocation},
+    from_rdf::{LiteralKind, Term},
+    proto::{
+        PhysicalStreamType, RdfIri, RdfLiteral, RdfNameEntry, RdfNamespaceDeclaration,
+        RdfPrefixEntry, RdfStreamFrame, RdfStreamOptions, RdfStreamRow, RdfTriple,
+        rdf_stream_row::Row, rdf_triple as t,
+    },
+    serialize::{FramingPolicy, Serializer},
+    to_rdf::{SharedRdf, SharedTerm, StringRdf, ToRdf},
+};
+
+fn frame(rows: Vec<Row>) -> RdfStreamFrame {
+    RdfStreamFrame {
+        rows: rows
+            .into_iter()
+            .map(|row| RdfStreamRow { row: Some(row) })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn options() -> Row {
+    Row::Options(RdfStreamOptions {
+        version: 2,
+        ..Serializer::default_options(PhysicalStreamType::Triples)
+    })
+}
+
+fn prefix(id: u32, value: &str) -> Row {
+    Row::Prefix(RdfPrefixEntry {
+        id,
+        value: value.to_string(),
+    })
+}
+
+fn name(id: u32, value: &str) -> Row {
+    Row::Name(RdfNameEntry {
+        id,
+        value: value.to_string(),
+    })
+}
+
+fn namespace(name: &str, prefix_id: u32, name_id: u32) -> Row {
+    Row::Namespace(RdfNamespaceDeclaration {
+        name: name.to_string(),
+        value: Some(RdfIri { prefix_id, name_id }),
+    })
+}
+
+#[test]
+fn namespaces_are_collected() {
+    let frame = frame(vec![
+        options(),
+        prefix(0, "http://example.org/"),
+        prefix(0, "http://xmlns.com/foaf/0.1/"),
+        name(0, ""),
+        name(0, "name"),
+        namespace("ex", 1, 1),
+        namespace("foaf", 2, 1),
+        namespace("ex", 1, 1),
+        // Prefix 0 is the previous prefix, name 0 the one after the previous name
+        Row::Triple(RdfTriple {
+            subject: Some(t::Subject::SIri(RdfIri {
+                prefix_id: 0,
+                name_id: 1,
+            })),
+            predicate: Some(t::Predicate::PIri(RdfIri {
+                prefix_id: 2,
+                name_id: 0,
+            })),
+            object: Some(t::Object::OLiteral(RdfLiteral {
+                lex: "x".to_string(),
+                literal_kind: None,
+            })),
+        }),
+    ]);
+
+    let mut out = Vec::new();
+    let mut handler = PrefixCollector::new(&mut out);
+    let mut des = Deserializer::<StringRdf>::new();
+    des.handle_frame(frame, &mut handler).expect("valid frame");
+
+    let mut prefixes: Vec<_> = handler.prefixes.into_iter().collect();
+    prefixes.sort();
+    assert_eq!(
+        out,
+        vec![(
+            "<http://example.org/>".to_string(),
+            "<http://xmlns.com/foaf/0.1/name>".to_string(),
+            "\"x\"".to_string(),
+            None
+        )]
+    );
+    assert_eq!(
+        prefixes,
+        vec![
+            ("ex".to_string(), "http://example.org/".to_string()),
+            ("foaf".to_string(), "http://xmlns.com/foaf/0.1/".to_string()),
+        ]
+    );
+}
+
+fn iri_triple(prefix_id: u32, name_id: u32) -> Row {
+    let iri = || RdfIri { prefix_id, name_id };
+    Row::Triple(RdfTriple {
+        subject: Some(t::Subject::SIri(iri())),
+        predicate: Some(t::Predicate::PIri(iri())),
+        object: Some(t::Object::OIri(iri())),
+    })
+}
+
+#[test]
+fn overwritten_entries_change_iris() {
+    let frame = frame(vec![
+        options(),
+        prefix(1, "http://example.org/"),
+        prefix(2, "http://example.com/"),
+        name(1, "a"),
+        iri_triple(1, 1),
+        iri_triple(2, 1),
+        name(1, "b"),
+        iri_triple(1, 1),
+        prefix(1, "http://example.net/"),
+        iri_triple(1, 1),
+    ]);
+
+    let expected: Vec<_> = [
+        "http://example.org/a",
+        "http://example.com/a",
+        "http://example.org/b",
+        "http://example.net/b",
+    ]
+    .map(|iri| (iri.to_string(), iri.to_string()))
+    .into();
+
+    let mut out = Vec::new();
+    let mut des = Deserializer::<StringRdf>::new();
+    des.handle_frame(frame.clone(), &mut out)
+        .expect("valid frame");
+    let terms: Vec<_> = out
+        .into_iter()
+        .map(|(s, _, o, _)| (s[1..s.len() - 1].to_string(), o[1..o.len() - 1].to_string()))
+        .collect();
+    assert_eq!(terms, expected);
+
+    #[cfg(feature = "sophia")]
+    for iri_cache in [true, false] {
+        use jelly::to_rdf::SophiaRdf;
+        use sophia_term::ArcTerm;
+
+        let iri = |term: &ArcTerm| match term {
+            ArcTerm::Iri(iri) => iri.as_str().to_string(),
+            _ => panic!("IRI term"),
+        };
+        let mut terms = Vec::new();
+        let mut handler = (
+            |[s, _, o]: [ArcTerm; 3]| terms.push((iri(&s), iri(&o))),
+            |_: <SophiaRdf as ToRdf>::Quad<'_>| {},
+        );
+        let mut des = Deserializer::<SophiaRdf>::new();
+        des.set_iri_cache(iri_cache);
+        des.handle_frame(frame.clone(), &mut handler)
+            .expect("valid frame");
+        // The object is the third use of the IRI, after it was kept by the cache
+        assert_eq!(terms, expected);
+    }
+}
+
+#[derive(Default)]
+struct Events(Vec<String>);
+
+impl RdfHandler<StringRdf> for &mut Events {
+    fn handle_triple<'b>(&mut self, (s, _, _): <StringRdf as ToRdf>::Triple<'b>) {
+        self.0.push(format!("triple {}", s));
+    }
+
+    fn handle_quad<'b>(&mut self, (s, _, _, _): <StringRdf as ToRdf>::Quad<'b>) {
+        self.0.push(format!("quad {}", s));
+    }
+
+    fn handle_graph_start(&mut self, graph: Option<&String>) {
+        self.0.push(format!("graph start {:?}", graph));
+    }
+
+    fn handle_graph_end(&mut self) {
+        self.0.push("graph end".to_string());
+    }
+
+    fn handle_frame_start(&mut self, metadata: &HashMap<String, Vec<u8>>) {
+        self.0.push(format!("frame start {:?}", metadata.get("n")));
+    }
+
+    fn handle_frame_end(&mut self) {
+        self.0.push("frame end".to_string());
+    }
+}
+
+#[test]
+fn graph_and_frame_events() {
+    let mut ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Graphs))
+        .expect("valid options");
+    ser.set_framing_policy(FramingPolicy::PerGraph);
+    let s = Term::Iri("http://example.org/s".to_string());
+    let g = Term::Iri("http://example.org/g".to_string());
+    ser.quad(&s, &s, &s, Some(&g)).unwrap();
+    ser.quad(&s, &s, &s, None::<&Term>).unwrap();
+
+    let mut frames = ser.finish();
+    frames[1].metadata.insert("n".to_string(), vec![1]);
+
+    let mut events = Events::default();
+    let mut des = Deserializer::<StringRdf>::new();
+    for frame in frames {
+        des.handle_frame(frame, &mut events).expect("valid frame");
+    }
+
+    let s = "<http://example.org/s>";
+    assert_eq!(
+        events.0,
+        vec![
+            "frame start None".to_string(),
+            "graph start Some(\"<http://example.org/g>\")".to_string(),
+            format!("quad {}", s),
+            "graph end".to_string(),
+            "frame end".to_string(),
+            "frame start Some([1])".to_string(),
+            "graph start None".to_string(),
+            format!("quad {}", s),
+            "graph end".to_string(),
+            "frame end".to_string(),
+        ]
+    );
+}
+
+#[derive(Debug, PartialEq)]
+struct TooMany(usize);
+
+impl std::fmt::Display for TooMany {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "more than {} triples", self.0)
+    }
+}
+
+impl std::error::Error for TooMany {}
+
+/// Accepts up to `limit` triples.
+struct Limit {
+    limit: usize,
+    seen: usize,
+}
+
+impl TryRdfHandler<StringRdf> for &mut Limit {
+    type Error = TooMany;
+
+    fn try_handle_triple<'b>(
+        &mut self,
+        _: <StringRdf as ToRdf>::Triple<'b>,
+    ) -> Result<(), Self::Error> {
+        if self.seen == self.limit {
+            return Err(TooMany(self.limit));
+        }
+        self.seen += 1;
+        Ok(())
+    }
+
+    fn try_handle_quad<'b>(
+        &mut self,
+        _: <StringRdf as ToRdf>::Quad<'b>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn handler_errors_stop_decoding() {
+    let mut ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Triples))
+        .expect("valid options");
+    for i in 0..3 {
+        let s = Term::Iri(format!("http://example.org/s{}", i));
+        ser.triple(&s, &s, &s).unwrap();
+    }
+    let frame = ser.finish().remove(0);
+
+    let mut limit = Limit { limit: 2, seen: 0 };
+    let mut des = Deserializer::<StringRdf>::new();
+    let Err(error) = des.handle_frame(frame.clone(), &mut limit) else {
+        panic!("handler error is returned");
+    };
+    let DeserializeError::Handler(err) = error.kind() else {
+        panic!("handler error is returned");
+    };
+    assert_eq!(err.downcast_ref(), Some(&TooMany(2)));
+    let source = std::error::Error::source(error.kind()).expect("handler error is the source");
+    assert_eq!(source.downcast_ref(), Some(&TooMany(2)));
+    assert_eq!(limit.seen, 2);
+
+    limit = Limit { limit: 3, seen: 0 };
+    let mut des = Deserializer::<StringRdf>::new();
+    des.handle_frame(frame, &mut limit).expect("within limit");
+    assert_eq!(limit.seen, 3);
+}
+
+/// Decodes a triple with `subject` from a stream that enables RDF-star, if `rdf_star`
+fn subject_triple(
+    subject: t::Subject,
+    rdf_star: bool,
+    lenient: bool,
+) -> Result<usize, DeserializeError> {
+    let iri = || RdfIri {
+        prefix_id: 1,
+        name_id: 1,
+    };
+    let frame = frame(vec![
+        Row::Options(RdfStreamOptions {
+            rdf_star,
+            version: 2,
+            ..Serializer::default_options(PhysicalStreamType::Triples)
+        }),
+        prefix(0, "http://example.org/"),
+        name(0, "s"),
+        Row::Triple(RdfTriple {
+            subject: Some(subject),
+            predicate: Some(t::Predicate::PIri(iri())),
+            object: Some(t::Object::OIri(iri())),
+        }),
+    ]);
+
+    let mut des = Deserializer::<StringRdf>::new();
+    des.set_lenient(lenient);
+    let mut out = Vec::new();
+    des.handle_frame(frame, &mut out)?;
+    Ok(out.len())
+}
+
+#[test]
+fn term_positions_follow_options() {
+    let literal = || RdfLiteral {
+        lex: "x".to_string(),
+        literal_kind: None,
+    };
+    let iri = || RdfIri {
+        prefix_id: 1,
+        name_id: 1,
+    };
+    let quoted = |subject| {
+        t::Subject::STripleTerm(Box::new(RdfTriple {
+            subject: Some(subject),
+            predicate: Some(t::Predicate::PIri(iri())),
+            object: Some(t::Object::OIri(iri())),
+        }))
+    };
+
+    // Literals are only allowed as subjects with generalized statements, also inside of
+    // a triple term
+    for subject in [
+        t::Subject::SLiteral(literal()),
+        quoted(t::Subject::SLiteral(literal())),
+    ] {
+        assert!(matches!(
+            subject_triple(subject.clone(), true, false)
+                .as_ref()
+                .map_err(DeserializeError::kind),
+            Err(DeserializeError::GeneralizedNotEnabled {
+                location: TermLocation::Subject,
+                kind: TermKind::Literal,
+            })
+        ));
+        assert_eq!(subject_triple(subject, true, true).unwrap(), 1);
+    }
+
+    let subject = quoted(t::Subject::SIri(iri()));
+    assert_eq!(subject_triple(subject.clone(), true, false).unwrap(), 1);
+    assert!(matches!(
+        subject_triple(subject.clone(), false, false)
+            .as_ref()
+            .map_err(DeserializeError::kind),
+        Err(DeserializeError::RdfStarNotEnabled(TermLocation::Subject))
+    ));
+    assert_eq!(subject_triple(subject, false, true).unwrap(), 1);
+}
+
+#[test]
+fn repeated_options_grow_tables() {
+    let sized = |max_name_table_size| {
+        Row::Options(RdfStreamOptions {
+            max_name_table_size,
+            version: 2,
+            ..Serializer::default_options(PhysicalStreamType::Triples)
+        })
+    };
+    let s = "<http://example.org/s>".to_string();
+
+    let mut des = Deserializer::<StringRdf>::new();
+    let mut out = Vec::new();
+    let rows = vec![
+        sized(8),
+        prefix(0, "http://example.org/"),
+        name(1, "s"),
+        iri_triple(1, 1),
+        sized(16),
+        name(12, "s"),
+        iri_triple(1, 12),
+        sized(16),
+        iri_triple(1, 12),
+    ];
+    des.handle_frame(frame(rows), &mut out)
+        .expect("growing tables are accepted");
+    assert_eq!(out, vec![(s.clone(), s.clone(), s.clone(), None); 3]);
+
+    let shrunk = des.handle_frame(frame(vec![sized(8)]), &mut out);
+    assert!(matches!(
+        shrunk.as_ref().map_err(DeserializeError::kind),
+        Err(DeserializeError::ConfigError(ConfigError::OptionsChanged(
+            "max_name_table_size"
+        )))
+    ));
+}
+
+#[test]
+fn shared_terms() {
+    let mut ser = Serializer::new(RdfStreamOptions {
+        rdf_star: true,
+        ..Serializer::default_options(PhysicalStreamType::Triples)
+    })
+    .expect("valid options");
+    let s = Term::Iri("http://example.org/people/alice".to_string());
+    let p = Term::Iri("http://xmlns.com/foaf/0.1/name".to_string());
+    let objects = [
+        Term::Literal(
+            "Alice \"A\"".to_string(),
+            LiteralKind::Language("en".to_string()),
+        ),
+        Term::Literal(
+            "42".to_string(),
+            LiteralKind::Datatype("http://www.w3.org/2001/XMLSchema#integer".to_string()),
+        ),
+        Term::BlankNode("b1".to_string()),
+        Term::Triple(Box::new([s.clone(), p.clone(), s.clone()])),
+    ];
+    for o in &objects {
+        ser.triple(&s, &p, o).unwrap();
+    }
+    let frames = ser.finish();
+
+    let mut shared: Vec<[SharedTerm; 3]> = Vec::new();
+    let mut handler = (
+        |triple: [&SharedTerm; 3]| {
+            assert!(triple[1].is_iri("http://xmlns.com/foaf/0.1/name"));
+            assert!(!triple[1].is_iri("http://xmlns.com/foaf/0.1/nam"));
+            shared.push(triple.map(SharedTerm::to_owned));
+        },
+        |_: <SharedRdf as ToRdf>::Quad<'_>| {},
+    );
+    let mut des = Deserializer::<SharedRdf>::new();
+    for frame in frames.clone() {
+        des.handle_frame(frame, &mut handler).expect("valid frame");
+    }
+
+    let mut strings = Vec::new();
+    let mut des = Deserializer::<StringRdf>::new();
+    for frame in frames {
+        des.handle_frame(frame, &mut strings).expect("valid frame");
+    }
+    assert_eq!(shared.len(), strings.len());
+    for (terms, (s, p, o, _)) in shared.iter().zip(&strings) {
+        assert_eq!(terms[0].to_string(), *s);
+        assert_eq!(terms[1].to_string(), *p);
+        assert_eq!(terms[2].to_string(), *o);
+    }
+
+    let (prefix, name) = shared[0][0].iri().expect("IRI subject");
+    assert_eq!(
+        format!("{}{}", prefix, name),
+        "http://example.org/people/alice"
+    );
+    for (terms, o) in shared.iter().zip(&objects) {
+        assert_eq!(terms[0].to_term(), s);
+        assert_eq!(terms[2].to_term(), *o);
+    }
+}
+
+#[test]
+fn shared_iris_compare_joined() {
+    use std::collections::HashSet;
+    use std::hash::{BuildHasher, RandomState};
+
+    let iri = |prefix: &str, name: &str| SharedTerm::Iri {
+        prefix: prefix.into(),
+        name: name.into(),
+    };
+    let long = "http://example.org/a/very/long/path/to/some/resource/";
+    let splits = [
+        iri("", &format!("{}alice", long)),
+        iri(long, "alice"),
+        iri("http://example.org/", &format!("{}alice", &long[19..])),
+        iri(&format!("{}alice", long), ""),
+    ];
+    let hasher = RandomState::new();
+    for a in &splits {
+        for b in &splits {
+            assert_eq!(a, b);
+            assert_eq!(hasher.hash_one(a), hasher.hash_one(b));
+        }
+    }
+
+    let others = [
+        iri(long, "alic"),
+        iri(long, "alicee"),
+        iri("http://example.org/", "alice"),
+        SharedTerm::BlankNode(format!("{}alice", long)),
+    ];
+    for other in &others {
+        assert_ne!(&splits[1], other);
+    }
+    let set: HashSet<_> = splits.iter().chain(&others).collect();
+    assert_eq!(set.len(), 1 + others.len());
+}
+
+#[cfg(feature = "sophia")]
+#[test]
+fn sophia_literals_share_datatypes_and_tags() {
+    use std::sync::Arc;
+
+    use jelly::proto::{RdfDatatypeEntry, rdf_literal::LiteralKind as Kind};
+    use jelly::to_rdf::SophiaRdf;
+    use sophia_term::{ArcTerm, GenericLiteral};
+
+    let datatype = |id: u32, value: &str| {
+        Row::Datatype(RdfDatatypeEntry {
+            id,
+            value: value.to_string(),
+        })
+    };
+    let literal = |kind: Kind| {
+        Row::Triple(RdfTriple {
+            subject: Some(t::Subject::SIri(RdfIri {
+                prefix_id: 1,
+                name_id: 1,
+            })),
+            predicate: Some(t::Predicate::PIri(RdfIri {
+                prefix_id: 0,
+                name_id: 1,
+            })),
+            object: Some(t::Object::OLiteral(RdfLiteral {
+                lex: "1".to_string(),
+                literal_kind: Some(kind),
+            })),
+        })
+    };
+    let rows = frame(vec![
+        options(),
+        prefix(1, "http://example.org/"),
+        name(1, "a"),
+        datatype(1, "http://www.w3.org/2001/XMLSchema#integer"),
+        literal(Kind::Datatype(1)),
+        literal(Kind::Langtag("en".to_string())),
+        literal(Kind::Datatype(1)),
+        literal(Kind::Langtag("en".to_string())),
+        datatype(1, "http://www.w3.org/2001/XMLSchema#decimal"),
+        literal(Kind::Datatype(1)),
+    ]);
+
+    let mut objects = Vec::new();
+    let mut handler = (
+        |[_, _, o]: [ArcTerm; 3]| objects.push(o),
+        |_: <SophiaRdf as ToRdf>::Quad<'_>| {},
+    );
+    let mut des = Deserializer::<SophiaRdf>::new();
+    des.handle_frame(rows, &mut handler).expect("valid frame");
+    // An overwritten entry is validated again
+    let invalid = frame(vec![datatype(1, "not an iri"), literal(Kind::Datatype(1))]);
+    assert!(des.handle_frame(invalid, &mut handler).is_err());
+
+    let datatypes: Vec<Arc<str>> = objects
+        .iter()
+        .filter_map(|o| match o {
+            ArcTerm::Literal(GenericLiteral::Typed(_, datatype)) => Some(datatype.clone().unwrap()),
+            _ => None,
+        })
+        .collect();
+    let tags: Vec<Arc<str>> = objects
+        .iter()
+        .filter_map(|o| match o {
+            ArcTerm::Literal(GenericLiteral::LanguageString(_, tag, _)) => {
+                Some(tag.clone().unwrap())
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(datatypes.len(), 3);
+    assert!(Arc::ptr_eq(&datatypes[0], &datatypes[1]));
+    assert_eq!(&*datatypes[2], "http://www.w3.org/2001/XMLSchema#decimal");
+    assert_eq!(tags.len(), 2);
+    assert!(Arc::ptr_eq(&tags[0], &tags[1]));
+}
+
+#[cfg(feature = "oxrdf")]
+#[test]
+fn oxrdf_blank_nodes() {
+    use jelly::to_rdf::OxRdf;
+    use oxrdf::{Subject, Term as OxTerm, Triple};
+
+    let bnodes = |s: &str, o: &str| {
+        Row::Triple(RdfTriple {
+            subject: Some(t::Subject::SBnode(s.to_string())),
+            predicate: Some(t::Predicate::PIri(RdfIri {
+                prefix_id: 1,
+                name_id: 1,
+            })),
+            object: Some(t::Object::OBnode(o.to_string())),
+        })
+    };
+    let frame = frame(vec![
+        options(),
+        prefix(0, "http://example.org/"),
+        name(0, "p"),
+        bnodes("b0", "not a label"),
+        bnodes("b1", "not a label"),
+    ]);
+
+    let mut triples = Vec::new();
+    let mut handler = (|triple: Triple| triples.push(triple), |_: oxrdf::Quad| {});
+    let mut des = Deserializer::<OxRdf>::new();
+    des.handle_frame(frame, &mut handler).expect("valid frame");
+
+    let subjects: Vec<_> = triples
+        .iter()
+        .map(|triple| match &triple.subject {
+            Subject::BlankNode(bnode) => bnode.as_str().to_string(),
+            subject => panic!("blank node expected, got {}", subject),
+        })
+        .collect();
+    assert_eq!(subjects, ["b0", "b1"]);
+    // Labels oxrdf rejects get the same replacement every time
+    let OxTerm::BlankNode(replaced) = &triples[0].object else {
+        panic!("blank node expected");
+    };
+    assert_ne!(replaced.as_str(), "not a label");
+    assert_eq!(triples[0].object, triples[1].object);
+}
+
+#[cfg(feature = "oxrdf")]
+#[test]
+fn oxrdf_validates_iris() {
+    use jelly::to_rdf::OxRdf;
+
+    let mut triples = Vec::new();
+    let mut handler = (
+        |triple: oxrdf::Triple| triples.push(triple),
+        |_: oxrdf::Quad| {},
+    );
+    let mut des = Deserializer::<OxRdf>::new();
+    let valid = frame(vec![
+        options(),
+        prefix(1, "http://example.org/"),
+        name(1, "a"),
+        iri_triple(1, 1),
+        iri_triple(1, 1),
+    ]);
+    des.handle_frame(valid, &mut handler).expect("valid frame");
+
+    // An overwritten entry is validated again
+    let invalid = frame(vec![name(1, "not an iri"), iri_triple(1, 1)]);
+    let error = des
+        .handle_frame(invalid, &mut handler)
+        .err()
+        .expect("invalid IRI");
+    assert!(matches!(error.kind(), DeserializeError::OxInvalidIri(_)));
+    assert_eq!(triples.len(), 2);
+    assert_eq!(triples[0], triples[1]);
+    assert_eq!(triples[0].predicate.as_str(), "http://example.org/a");
+}
@@ -0,0 +1,284 @@
+use std::io::Cursor;
+
+use prost::Message as _;
+
+use jelly::{
+    FrameReader, FrameWriter,
+    error::FrameReadError,
+    io::{ReaderLimits, is_delimited},
+    proto::RdfStreamFrame,
+};
+
+mod common;
+use common::frames;
+
+#[test]
+fn writer_round_trips_with_reader() {
+    let frames = frames(100);
+    assert!(frames.len() > 1);
+
+    let mut writer = FrameWriter::new(Vec::new());
+    writer.write_frames(frames.clone()).unwrap();
+    let bytes = writer.into_inner().unwrap();
+
+    let read: Vec<_> = FrameReader::new(Cursor::new(bytes))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(read, frames);
+}
+
+#[test]
+fn writer_flushes_frames_into_pipe() {
+    let frames = frames(100);
+    let (reader, pipe) = std::io::pipe().unwrap();
+
+    let written = frames.clone();
+    let producer = std::thread::spawn(move || {
+        let mut writer = FrameWriter::new(pipe);
+        writer.set_flush_after_frame(true);
+        for frame in &written {
+            writer.write_frame(frame).unwrap();
+        }
+    });
+
+    let read: Vec<_> = FrameReader::new(reader).collect::<Result<_, _>>().unwrap();
+    producer.join().unwrap();
+    assert_eq!(read, frames);
+}
+
+fn encoded(frames: &[RdfStreamFrame]) -> Vec<u8> {
+    let mut writer = FrameWriter::new(Vec::new());
+    writer.write_frames(frames.iter().cloned()).unwrap();
+    writer.into_inner().unwrap()
+}
+
+fn read_all(bytes: Vec<u8>) -> (usize, Option<FrameReadError>) {
+    let mut ok = 0;
+    for frame in FrameReader::new(Cursor::new(bytes)) {
+        match frame {
+            Ok(_) => ok += 1,
+            Err(e) => return (ok, Some(e)),
+        }
+    }
+    (ok, None)
+}
+
+#[test]
+fn reader_reports_corruption() {
+    let frames = frames(20);
+    let bytes = encoded(&frames);
+    let first = frames[0].encoded_len();
+    let second_start = (first + prost::length_delimiter_len(first)) as u64;
+
+    assert!(matches!(read_all(Vec::new()), (0, None)));
+    assert!(matches!(read_all(bytes.clone()), (n, None) if n == frames.len()));
+
+    let mut truncated = bytes.clone();
+    truncated.truncate(second_start as usize);
+    // Continuation bit set, but no further byte
+    truncated.push(0x80);
+    assert!(matches!(
+        read_all(truncated),
+        (1, Some(FrameReadError::TruncatedLength { offset })) if offset == second_start
+    ));
+
+    let mut truncated = bytes.clone();
+    truncated.truncate(second_start as usize + 10);
+    let prefix = prost::length_delimiter_len(frames[1].encoded_len()) as u64;
+    assert!(matches!(
+        read_all(truncated),
+        (1, Some(FrameReadError::TruncatedFrame { offset, read, .. }))
+            if offset == second_start && read == 10 - prefix
+    ));
+
+    assert!(matches!(
+        read_all(vec![0xff; 12]),
+        (0, Some(FrameReadError::InvalidLength { offset: 0 }))
+    ));
+
+    assert!(matches!(
+        read_all(vec![0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+        (0, Some(FrameReadError::FrameTooLarge { offset: 0, .. }))
+    ));
+
+    // Length 2, then a field with the invalid wire type 7
+    assert!(matches!(
+        read_all(vec![0x02, 0x0f, 0x00]),
+        (0, Some(FrameReadError::Decode { offset: 0, .. }))
+    ));
+}
+
+#[test]
+fn non_delimited_round_trip() {
+    let mut frames = frames(2);
+    assert_eq!(frames.len(), 1);
+    let frame = frames.remove(0);
+
+    let mut writer = FrameWriter::non_delimited(Vec::new());
+    writer.write_frame(&frame).unwrap();
+    assert!(writer.write_frame(&frame).is_err());
+    let bytes = writer.into_inner().unwrap();
+
+    let read: Vec<_> = FrameReader::non_delimited(Cursor::new(bytes.clone()))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(read, vec![frame.clone()]);
+
+    let reader = FrameReader::auto(Cursor::new(bytes)).unwrap();
+    assert!(!reader.is_delimited());
+    let read: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+    assert_eq!(read, vec![frame]);
+}
+
+#[test]
+fn auto_detects_delimited() {
+    let frames = frames(20);
+    let reader = FrameReader::auto(Cursor::new(encoded(&frames))).unwrap();
+    assert!(reader.is_delimited());
+    let read: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+    assert_eq!(read, frames);
+
+    assert!(is_delimited(&[0x0A, 0x0A, 0x12]));
+    assert!(!is_delimited(&[0x0A, 0x0A, 0x0A]));
+    assert!(!is_delimited(&[0x0A, 0x12, 0x0A]));
+    assert!(is_delimited(&[0x20, 0x0A, 0x0A]));
+}
+
+#[test]
+fn auto_detects_short_input() {
+    for scout in [
+        &[][..],
+        &[0x0A],
+        &[0x20],
+        &[0x0A, 0x0A],
+        &[0x0A, 0x12],
+        &[0x20, 0x0A],
+    ] {
+        assert!(!is_delimited(scout), "{:02X?}", scout);
+    }
+
+    let reader = FrameReader::auto(Cursor::new(Vec::new())).unwrap();
+    assert!(!reader.is_delimited());
+    assert_eq!(reader.count(), 0);
+
+    // Two bytes are decoded as a single frame, here one with an invalid field number
+    let mut reader = FrameReader::auto(Cursor::new(vec![0x00, 0x00])).unwrap();
+    assert!(!reader.is_delimited());
+    assert!(matches!(
+        reader.next(),
+        Some(Err(FrameReadError::Decode { .. }))
+    ));
+    assert!(reader.next().is_none());
+}
+
+fn read_limited(bytes: Vec<u8>, limits: ReaderLimits) -> Option<FrameReadError> {
+    let mut reader = FrameReader::new(Cursor::new(bytes));
+    reader.set_limits(limits);
+    reader.find_map(Result::err)
+}
+
+#[test]
+fn reader_enforces_limits() {
+    let frames = frames(20);
+    let bytes = encoded(&frames);
+    let largest = frames.iter().map(|f| f.encoded_len()).max().unwrap() as u64;
+
+    assert!(read_limited(bytes.clone(), ReaderLimits::default()).is_none());
+
+    // A huge length prefix is rejected before anything is allocated
+    let limits = ReaderLimits {
+        max_frame_bytes: 1024,
+        ..Default::default()
+    };
+    assert!(matches!(
+        read_limited(vec![0xff, 0xff, 0xff, 0xff, 0x07], limits),
+        Some(FrameReadError::FrameTooLarge { max: 1024, .. })
+    ));
+
+    let limits = ReaderLimits {
+        max_frame_bytes: largest - 1,
+        ..Default::default()
+    };
+    assert!(matches!(
+        read_limited(bytes.clone(), limits),
+        Some(FrameReadError::FrameTooLarge { .. })
+    ));
+
+    let limits = ReaderLimits {
+        max_rows_per_frame: 4,
+        ..Default::default()
+    };
+    assert!(matches!(
+        read_limited(bytes.clone(), limits),
+        Some(FrameReadError::TooManyRows {
+            offset: 0,
+            max: 4,
+            ..
+        })
+    ));
+
+    let limits = ReaderLimits {
+        max_stream_bytes: bytes.len() as u64 - 1,
+        ..Default::default()
+    };
+    assert!(matches!(
+        read_limited(bytes.clone(), limits),
+        Some(FrameReadError::StreamTooLarge { .. })
+    ));
+
+    // The only prefix is "http://example.org/"
+    let limits = ReaderLimits {
+        max_string_len: 18,
+        ..Default::default()
+    };
+    assert!(matches!(
+        read_limited(bytes.clone(), limits),
+        Some(FrameReadError::StringTooLong {
+            len: 19,
+            max: 18,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn reader_limits_trip_before_decoding() {
+    // A valid frame followed by a field with the invalid wire type 7, so the limits are
+    // only reported if they are checked before decoding
+    let mut frame = frames(20).remove(0).encode_to_vec();
+    frame.push(0x0f);
+    let mut bytes = Vec::new();
+    prost::encoding::encode_varint(frame.len() as u64, &mut bytes);
+    bytes.extend(frame);
+
+    assert!(matches!(
+        read_limited(bytes.clone(), ReaderLimits::default()),
+        Some(FrameReadError::Decode { offset: 0, .. })
+    ));
+
+    let limits = ReaderLimits {
+        max_rows_per_frame: 4,
+        ..Default::default()
+    };
+    assert!(matches!(
+        read_limited(bytes.clone(), limits),
+        Some(FrameReadError::TooManyRows {
+            offset: 0,
+            rows: 5,
+            max: 4,
+        })
+    ));
+
+    let limits = ReaderLimits {
+        max_string_len: 18,
+        ..Default::default()
+    };
+    assert!(matches!(
+        read_limited(bytes, limits),
+        Some(FrameReadError::StringTooLong {
+            offset: 0,
+            len: 19,
+            max: 18,
+        })
+    ));
+}
@@ -0,0 +1,166 @@
+use jelly::{
+    deserialize::Deserializer,
+    error::{ConfigError, DeserializeError},
+    from_rdf::{LiteralKind, Term},
+    logical::LogicalFrame,
+    proto::{
+        LogicalStreamType, PhysicalStreamType, RdfStreamFrame, RdfStreamOptions,
+        rdf_stream_row::Row,
+    },
+    serialize::Serializer,
+    to_rdf::StringRdf,
+};
+
+fn iri(iri: &str) -> Term {
+    Term::Iri(iri.to_string())
+}
+
+fn options(physical: PhysicalStreamType, logical: LogicalStreamType) -> RdfStreamOptions {
+    RdfStreamOptions {
+        logical_type: logical as i32,
+        ..Serializer::default_options(physical)
+    }
+}
+
+/// Two frames with a triple each in graph `g0` and `g1`, and one in the default graph
+/// when `timestamps` is set.
+fn frames(
+    physical: PhysicalStreamType,
+    logical: LogicalStreamType,
+    timestamps: bool,
+) -> Vec<RdfStreamFrame> {
+    let mut ser = Serializer::new(options(physical, logical)).expect("valid options");
+    for i in 0..2 {
+        let s = iri(&format!("http://example.org/s{}", i));
+        let p = iri("http://example.org/p");
+        let g = iri(&format!("http://example.org/g{}", i));
+        if timestamps {
+            let created = Term::Literal("2025-01-01T00:00:00Z".to_string(), LiteralKind::Simple);
+            ser.quad(&g, &p, &created, None::<&Term>).unwrap();
+        }
+        ser.quad(&s, &p, &g, Some(&g)).unwrap();
+        if physical == PhysicalStreamType::Graphs {
+            ser.end_graph().unwrap();
+        }
+        ser.flush();
+    }
+    ser.finish()
+}
+
+fn logical_frames(frames: Vec<RdfStreamFrame>) -> Vec<LogicalFrame<StringRdf>> {
+    let mut des = Deserializer::<StringRdf>::new();
+    frames
+        .into_iter()
+        .map(|frame| des.logical_frame(frame).expect("valid frame"))
+        .collect()
+}
+
+#[test]
+fn physical_type_must_fit_logical_type() {
+    for (physical, logical) in [
+        (PhysicalStreamType::Quads, LogicalStreamType::FlatTriples),
+        (PhysicalStreamType::Graphs, LogicalStreamType::SubjectGraphs),
+        (PhysicalStreamType::Triples, LogicalStreamType::Datasets),
+        (
+            PhysicalStreamType::Triples,
+            LogicalStreamType::TimestampedNamedGraphs,
+        ),
+    ] {
+        assert!(matches!(
+            Serializer::new(options(physical, logical)),
+            Err(ConfigError::IncompatibleLogicalType { .. })
+        ));
+    }
+
+    let mut frames = frames(
+        PhysicalStreamType::Quads,
+        LogicalStreamType::NamedGraphs,
+        false,
+    );
+    let Some(Row::Options(options)) = &mut frames[0].rows[0].row else {
+        panic!("stream starts with options");
+    };
+    options.physical_type = PhysicalStreamType::Triples as i32;
+    let mut des = Deserializer::<StringRdf>::new();
+    assert!(matches!(
+        des.logical_frame(frames.remove(0))
+            .as_ref()
+            .map_err(DeserializeError::kind),
+        Err(DeserializeError::ConfigError(
+            ConfigError::IncompatibleLogicalType { .. }
+        ))
+    ));
+}
+
+#[test]
+fn named_graph_per_frame() {
+    for physical in [PhysicalStreamType::Quads, PhysicalStreamType::Graphs] {
+        let graphs = logical_frames(frames(
+            physical,
+            LogicalStreamType::TimestampedNamedGraphs,
+            true,
+        ));
+        assert_eq!(graphs.len(), 2);
+
+        for (i, frame) in graphs.into_iter().enumerate() {
+            let LogicalFrame::NamedGraph {
+                name,
+                triples,
+                default_graph,
+            } = frame
+            else {
+                panic!("named graph frame");
+            };
+            let g = format!("<http://example.org/g{}>", i);
+            assert_eq!(name, Some(g.clone()));
+            assert_eq!(triples.len(), 1);
+            assert_eq!(default_graph.len(), 1);
+            assert_eq!(default_graph[0].0, g);
+        }
+    }
+}
+
+#[test]
+fn dataset_per_frame() {
+    let datasets = logical_frames(frames(
+        PhysicalStreamType::Quads,
+        LogicalStreamType::Datasets,
+        true,
+    ));
+    assert_eq!(datasets.len(), 2);
+    assert!(
+        datasets
+            .iter()
+            .all(|frame| matches!(frame, LogicalFrame::Dataset(quads) if quads.len() == 2))
+    );
+
+    // Two named graphs in one frame
+    let mut ser = Serializer::new(options(
+        PhysicalStreamType::Quads,
+        LogicalStreamType::NamedGraphs,
+    ))
+    .unwrap();
+    for i in 0..2 {
+        let g = iri(&format!("http://example.org/g{}", i));
+        ser.quad(&g, &g, &g, Some(&g)).unwrap();
+    }
+    let mut frames = ser.finish();
+    let mut des = Deserializer::<StringRdf>::new();
+    assert!(matches!(
+        des.logical_frame(frames.remove(0)),
+        Err(DeserializeError::MultipleNamedGraphs(
+            LogicalStreamType::NamedGraphs
+        ))
+    ));
+}
+
+#[test]
+fn flat_triples_without_logical_type() {
+    let mut ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Triples))
+        .expect("valid options");
+    let s = iri("http://example.org/s");
+    ser.triple(&s, &s, &s).unwrap();
+
+    let frames = logical_frames(ser.finish());
+    assert!(matches!(&frames[..], [LogicalFrame::Triples(triples)] if triples.len() == 1));
+}
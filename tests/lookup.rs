@@ -0,0 +1,129 @@
+use jelly::lookup::{Lookup, LookupEncoder, LookupType};
+
+const VALUES: [&str; 5] = ["a", "b", "c", "d", "e"];
+const LENGTH: u32 = 6;
+
+/// Encodes `sequence` in statements of `per_statement` values and decodes them again,
+/// failing statements are checked to really need more entries than the table has.
+fn round_trip(sequence: &[&str], size: u32, ty: LookupType, per_statement: usize) {
+    let mut encoder = LookupEncoder::new(size);
+    let mut decoder = Lookup::new(size);
+
+    for statement in sequence.chunks(per_statement) {
+        encoder.start_statement();
+
+        let mut entries = Vec::new();
+        let mut ids = Vec::new();
+        let mut failed = false;
+        for value in statement {
+            match encoder.encode(value, ty) {
+                Ok(encoded) => {
+                    if let Some(entry) = encoded.new_entry {
+                        entries.push((entry, value.to_string()));
+                    }
+                    ids.push((encoded.id, *value));
+                }
+                Err(_) => {
+                    failed = true;
+                    break;
+                }
+            }
+        }
+
+        for (entry, value) in entries {
+            decoder.set(entry, value).expect("entry fits in table");
+        }
+
+        if failed {
+            let mut distinct = statement.to_vec();
+            distinct.sort();
+            distinct.dedup();
+            assert!(
+                distinct.len() > size as usize,
+                "{:?} failed in {:?} with size {}",
+                statement,
+                sequence,
+                size
+            );
+            encoder.abort_statement();
+            continue;
+        }
+
+        for (id, value) in ids {
+            let decoded = decoder.get(id, ty).expect("id is valid");
+            assert_eq!(
+                decoded.as_ref(),
+                value,
+                "{:?} with size {} and {:?}",
+                sequence,
+                size,
+                ty
+            );
+        }
+    }
+}
+
+fn sequences() -> impl Iterator<Item = Vec<&'static str>> {
+    let count = VALUES.len().pow(LENGTH);
+    (0..count).map(|mut n| {
+        (0..LENGTH)
+            .map(|_| {
+                let value = VALUES[n % VALUES.len()];
+                n /= VALUES.len();
+                value
+            })
+            .collect()
+    })
+}
+
+#[test]
+fn encoder_round_trips_all_sequences() {
+    for sequence in sequences() {
+        for size in 1..=VALUES.len() as u32 {
+            for ty in [LookupType::Inc, LookupType::Stay, LookupType::Invalid] {
+                for per_statement in 1..=3 {
+                    round_trip(&sequence, size, ty, per_statement);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn encoder_uses_delta_ids() {
+    let mut names = LookupEncoder::new(4);
+    let first = names.encode("a", LookupType::Inc).unwrap();
+    let second = names.encode("b", LookupType::Inc).unwrap();
+    assert_eq!((first.id, first.new_entry), (0, Some(0)));
+    assert_eq!((second.id, second.new_entry), (0, Some(0)));
+    assert_eq!(names.encode("a", LookupType::Inc).unwrap().id, 1);
+
+    let mut prefixes = LookupEncoder::new(4);
+    assert_eq!(prefixes.encode("p", LookupType::Stay).unwrap().id, 1);
+    assert_eq!(prefixes.encode("p", LookupType::Stay).unwrap().id, 0);
+
+    let mut datatypes = LookupEncoder::new(4);
+    assert_eq!(datatypes.encode("d", LookupType::Invalid).unwrap().id, 1);
+    assert_eq!(datatypes.encode("d", LookupType::Invalid).unwrap().id, 1);
+}
+
+#[test]
+fn encoder_evicts_least_recently_used() {
+    let mut encoder = LookupEncoder::new(2);
+    for value in ["a", "b", "a", "c"] {
+        encoder.start_statement();
+        encoder.encode(value, LookupType::Invalid).unwrap();
+    }
+
+    // "b" was evicted for "c", "a" is still known
+    encoder.start_statement();
+    assert_eq!(
+        encoder.encode("a", LookupType::Invalid).unwrap().new_entry,
+        None
+    );
+    encoder.start_statement();
+    assert_eq!(
+        encoder.encode("b", LookupType::Invalid).unwrap().new_entry,
+        Some(2)
+    );
+}
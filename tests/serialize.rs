@@ -0,0 +1,587 @@
+use prost::Message as _;
+
+use jelly::{
+    deserialize::Deserializer,
+    error::{ConfigError, DeserializeError, SerializeError, TermLocation},
+    from_rdf::{LiteralKind, Term},
+    proto::{PhysicalStreamType, RdfStreamFrame, RdfStreamOptions, rdf_stream_row::Row},
+    serialize::{FramingPolicy, Serializer},
+    to_rdf::StringRdf,
+};
+
+type Statement = (String, String, String, Option<String>);
+
+fn iri(iri: &str) -> Term {
+    Term::Iri(iri.to_string())
+}
+
+fn literal(lex: &str, kind: LiteralKind) -> Term {
+    Term::Literal(lex.to_string(), kind)
+}
+
+fn decode(frames: Vec<RdfStreamFrame>) -> Vec<Statement> {
+    let mut des = Deserializer::<StringRdf>::new();
+    let mut out = Vec::new();
+    for frame in frames {
+        des.handle_frame(frame, &mut out).expect("valid frame");
+    }
+    out
+}
+
+fn options(name: u32, prefix: u32, datatype: u32) -> RdfStreamOptions {
+    RdfStreamOptions {
+        physical_type: PhysicalStreamType::Triples as i32,
+        max_name_table_size: name,
+        max_prefix_table_size: prefix,
+        max_datatype_table_size: datatype,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn triples_round_trip() {
+    let mut ser = Serializer::new(RdfStreamOptions {
+        rdf_star: true,
+        ..Serializer::default_options(PhysicalStreamType::Triples)
+    })
+    .expect("valid options");
+
+    let s = iri("http://example.org/alice");
+    let knows = iri("http://xmlns.com/foaf/0.1/knows");
+    let name = iri("http://xmlns.com/foaf/0.1/name");
+    let age = iri("http://xmlns.com/foaf/0.1/age");
+    let bob = Term::BlankNode("bob".to_string());
+    let integer = LiteralKind::Datatype("http://www.w3.org/2001/XMLSchema#integer".to_string());
+    let quoted = Term::Triple(Box::new([s.clone(), knows.clone(), bob.clone()]));
+
+    ser.triple(&s, &knows, &bob).unwrap();
+    ser.triple(&s, &name, &literal("Alice \"A\"", LiteralKind::Simple))
+        .unwrap();
+    ser.triple(
+        &s,
+        &name,
+        &literal("Alicia", LiteralKind::Language("es".to_string())),
+    )
+    .unwrap();
+    ser.triple(&bob, &age, &literal("42", integer)).unwrap();
+    ser.triple(&quoted, &name, &literal("x", LiteralKind::Simple))
+        .unwrap();
+
+    let s = "<http://example.org/alice>".to_string();
+    let knows = "<http://xmlns.com/foaf/0.1/knows>".to_string();
+    let name = "<http://xmlns.com/foaf/0.1/name>".to_string();
+    let expected = vec![
+        (s.clone(), knows.clone(), "_:Bbob".to_string(), None),
+        (
+            s.clone(),
+            name.clone(),
+            "\"Alice \\\"A\\\"\"".to_string(),
+            None,
+        ),
+        (s.clone(), name.clone(), "\"Alicia\"@es".to_string(), None),
+        (
+            "_:Bbob".to_string(),
+            "<http://xmlns.com/foaf/0.1/age>".to_string(),
+            "\"42\"^^<http://www.w3.org/2001/XMLSchema#integer>".to_string(),
+            None,
+        ),
+        (
+            format!("<< {} {} _:Bbob >>", s, knows),
+            name,
+            "\"x\"".to_string(),
+            None,
+        ),
+    ];
+
+    assert_eq!(decode(ser.finish()), expected);
+}
+
+#[test]
+fn small_tables_evict_entries() {
+    let mut ser = Serializer::new(options(8, 3, 2)).expect("valid options");
+
+    let mut expected = Vec::new();
+    for i in 0..50 {
+        let s = iri(&format!("http://example.org/{}/s{}", i % 5, i % 11));
+        let p = iri(&format!("http://example.com/p{}", i % 3));
+        let o = literal(
+            &i.to_string(),
+            LiteralKind::Datatype(format!("http://example.org/dt{}", i % 4)),
+        );
+        ser.triple(&s, &p, &o).unwrap();
+        if i % 7 == 0 {
+            ser.flush();
+        }
+
+        expected.push((
+            format!("<http://example.org/{}/s{}>", i % 5, i % 11),
+            format!("<http://example.com/p{}>", i % 3),
+            format!("\"{}\"^^<http://example.org/dt{}>", i, i % 4),
+            None,
+        ));
+    }
+
+    let frames = ser.finish();
+    assert!(frames.len() > 1);
+    assert_eq!(decode(frames), expected);
+}
+
+#[test]
+fn disabled_prefix_table() {
+    let mut ser = Serializer::new(options(8, 0, 0)).expect("valid options");
+    let s = iri("http://example.org/s");
+    ser.triple(&s, &s, &literal("x", LiteralKind::Simple))
+        .unwrap();
+
+    let dt = LiteralKind::Datatype("http://example.org/dt".to_string());
+    assert!(ser.triple(&s, &s, &literal("x", dt)).is_err());
+
+    assert_eq!(
+        decode(ser.finish()),
+        vec![(
+            "<http://example.org/s>".to_string(),
+            "<http://example.org/s>".to_string(),
+            "\"x\"".to_string(),
+            None
+        )]
+    );
+}
+
+fn quads() -> Vec<(Term, Term, Term, Option<Term>)> {
+    let g1 = iri("http://example.org/g1");
+    let g2 = Term::BlankNode("g2".to_string());
+    let s = iri("http://example.org/s");
+    let p = iri("http://example.org/p");
+    vec![
+        (
+            s.clone(),
+            p.clone(),
+            literal("1", LiteralKind::Simple),
+            Some(g1.clone()),
+        ),
+        (
+            s.clone(),
+            p.clone(),
+            literal("2", LiteralKind::Simple),
+            None,
+        ),
+        (
+            s.clone(),
+            p.clone(),
+            literal("3", LiteralKind::Simple),
+            Some(g2.clone()),
+        ),
+        (
+            s.clone(),
+            p.clone(),
+            literal("4", LiteralKind::Simple),
+            Some(g1.clone()),
+        ),
+        (
+            s.clone(),
+            p.clone(),
+            literal("5", LiteralKind::Simple),
+            None,
+        ),
+    ]
+}
+
+fn expected(order: &[usize]) -> Vec<Statement> {
+    let graphs = [
+        Some("<http://example.org/g1>".to_string()),
+        None,
+        Some("_:Bg2".to_string()),
+        Some("<http://example.org/g1>".to_string()),
+        None,
+    ];
+    order
+        .iter()
+        .map(|&i| {
+            (
+                "<http://example.org/s>".to_string(),
+                "<http://example.org/p>".to_string(),
+                format!("\"{}\"", i + 1),
+                graphs[i].clone(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn quads_round_trip() {
+    let mut ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Quads))
+        .expect("valid options");
+    for (s, p, o, g) in quads() {
+        ser.quad(&s, &p, &o, g.as_ref()).unwrap();
+    }
+    assert!(
+        ser.triple(
+            &iri("http://example.org/s"),
+            &iri("http://example.org/p"),
+            &iri("http://example.org/o")
+        )
+        .is_err()
+    );
+
+    assert_eq!(decode(ser.finish()), expected(&[0, 1, 2, 3, 4]));
+}
+
+#[test]
+fn graphs_round_trip() {
+    let mut ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Graphs))
+        .expect("valid options");
+    for (s, p, o, g) in quads() {
+        ser.quad(&s, &p, &o, g.as_ref()).unwrap();
+    }
+
+    assert_eq!(decode(ser.finish()), expected(&[0, 1, 2, 3, 4]));
+}
+
+#[test]
+fn graphs_dataset_is_grouped() {
+    let mut ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Graphs))
+        .expect("valid options");
+    ser.dataset(quads()).unwrap();
+
+    let frames = ser.finish();
+    let starts = frames
+        .iter()
+        .flat_map(|frame| &frame.rows)
+        .filter(|row| matches!(row.row, Some(Row::GraphStart(_))))
+        .count();
+    assert_eq!(starts, 3);
+    assert_eq!(decode(frames), expected(&[0, 3, 1, 4, 2]));
+}
+
+fn elided_terms(frames: &[RdfStreamFrame]) -> usize {
+    frames
+        .iter()
+        .flat_map(|frame| &frame.rows)
+        .map(|row| match &row.row {
+            Some(Row::Triple(t)) => [
+                t.subject.is_none(),
+                t.predicate.is_none(),
+                t.object.is_none(),
+            ]
+            .into_iter()
+            .filter(|x| *x)
+            .count(),
+            Some(Row::Quad(q)) => [
+                q.subject.is_none(),
+                q.predicate.is_none(),
+                q.object.is_none(),
+                q.graph.is_none(),
+            ]
+            .into_iter()
+            .filter(|x| *x)
+            .count(),
+            _ => 0,
+        })
+        .sum()
+}
+
+#[test]
+fn repeated_terms_are_elided() {
+    let mut ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Quads))
+        .expect("valid options");
+    for (s, p, o, g) in quads() {
+        ser.quad(&s, &p, &o, g.as_ref()).unwrap();
+    }
+    let frames = ser.finish();
+
+    // subject and predicate of all but the first quad
+    assert_eq!(elided_terms(&frames), 8);
+    assert_eq!(decode(frames), expected(&[0, 1, 2, 3, 4]));
+
+    let mut ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Quads))
+        .expect("valid options");
+    ser.set_repeated_term_elision(false);
+    for (s, p, o, g) in quads() {
+        ser.quad(&s, &p, &o, g.as_ref()).unwrap();
+    }
+    let frames = ser.finish();
+
+    assert_eq!(elided_terms(&frames), 0);
+    assert_eq!(decode(frames), expected(&[0, 1, 2, 3, 4]));
+}
+
+fn framed(physical_type: PhysicalStreamType, framing: FramingPolicy) -> Vec<RdfStreamFrame> {
+    let mut ser = Serializer::new(Serializer::default_options(physical_type)).unwrap();
+    ser.set_framing_policy(framing);
+    for i in 0..20 {
+        let s = iri(&format!("http://example.org/s{}", i));
+        let p = iri(&format!("http://example.org/p{}", i % 3));
+        let g = iri(&format!("http://example.org/g{}", i / 5));
+        ser.quad(&s, &p, &literal("o", LiteralKind::Simple), Some(&g))
+            .unwrap();
+    }
+    let frames = ser.finish();
+
+    // Lookup entries are never left at the end of a frame without their statement
+    for frame in &frames {
+        assert!(matches!(
+            frame.rows.last().and_then(|row| row.row.as_ref()),
+            Some(Row::Quad(_) | Row::Triple(_) | Row::GraphEnd(_))
+        ));
+    }
+    assert_eq!(decode(frames.clone()).len(), 20);
+    frames
+}
+
+#[test]
+fn framing_policies() {
+    assert_eq!(
+        framed(PhysicalStreamType::Quads, FramingPolicy::Manual).len(),
+        1
+    );
+
+    let frames = framed(PhysicalStreamType::Quads, FramingPolicy::Rows(10));
+    assert!(frames.len() > 2);
+    assert!(
+        frames[..frames.len() - 1]
+            .iter()
+            .all(|frame| frame.rows.len() >= 10)
+    );
+
+    let frames = framed(PhysicalStreamType::Quads, FramingPolicy::Bytes(100));
+    assert!(frames.len() > 2);
+    assert!(
+        frames[..frames.len() - 1]
+            .iter()
+            .all(|frame| frame.encoded_len() >= 100)
+    );
+
+    let frames = framed(PhysicalStreamType::Graphs, FramingPolicy::PerGraph);
+    assert_eq!(frames.len(), 4);
+    for frame in &frames {
+        let starts = frame
+            .rows
+            .iter()
+            .filter(|row| matches!(row.row, Some(Row::GraphStart(_))))
+            .count();
+        assert_eq!(starts, 1);
+    }
+
+    // Only the first quad of a graph names it, the rest repeat it
+    let frames = framed(PhysicalStreamType::Quads, FramingPolicy::PerGraph);
+    assert_eq!(frames.len(), 4);
+    for frame in &frames {
+        let graphs = frame
+            .rows
+            .iter()
+            .filter(|row| matches!(&row.row, Some(Row::Quad(q)) if q.graph.is_some()))
+            .count();
+        assert_eq!(graphs, 1);
+    }
+}
+
+#[test]
+fn framing_per_dataset() {
+    for physical_type in [PhysicalStreamType::Quads, PhysicalStreamType::Graphs] {
+        let mut ser = Serializer::new(Serializer::default_options(physical_type)).unwrap();
+        ser.set_framing_policy(FramingPolicy::PerDataset);
+        ser.dataset(quads()).unwrap();
+        ser.dataset(quads()).unwrap();
+        let frames = ser.finish();
+        assert_eq!(frames.len(), 2);
+
+        let mut des = Deserializer::<StringRdf>::new();
+        for frame in frames {
+            let mut out = Vec::new();
+            des.handle_frame(frame, &mut out).expect("valid frame");
+            assert_eq!(out, expected(&[0, 3, 1, 4, 2]));
+        }
+    }
+}
+
+#[test]
+fn framing_policy_changes_within_a_frame() {
+    let mut ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Quads)).unwrap();
+    for (s, p, o, g) in quads() {
+        ser.quad(&s, &p, &o, g.as_ref()).unwrap();
+    }
+    assert_eq!(ser.frames().count(), 0);
+
+    // The rows written before still count towards the byte budget
+    ser.set_framing_policy(FramingPolicy::Bytes(100));
+    let (s, p, o, g) = &quads()[0];
+    ser.quad(s, p, o, g.as_ref()).unwrap();
+    let frames: Vec<_> = ser.frames().collect();
+    assert_eq!(frames.len(), 1);
+    let quads = frames[0]
+        .rows
+        .iter()
+        .filter(|row| matches!(row.row, Some(Row::Quad(_))))
+        .count();
+    assert_eq!(quads, 6);
+}
+
+#[test]
+fn term_positions_follow_options() {
+    let mut ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Quads))
+        .expect("valid options");
+    let s = iri("http://example.org/s");
+    let quoted = Term::Triple(Box::new([s.clone(), s.clone(), s.clone()]));
+    let lit = literal("x", LiteralKind::Simple);
+
+    assert!(matches!(
+        ser.quad(&s, &s, &quoted, None::<&Term>),
+        Err(SerializeError::RdfStarNotEnabled(TermLocation::Object))
+    ));
+    assert!(matches!(
+        ser.quad(&lit, &s, &s, None::<&Term>),
+        Err(SerializeError::GeneralizedNotEnabled {
+            location: TermLocation::Subject,
+            ..
+        })
+    ));
+    assert!(matches!(
+        ser.quad(&s, &s, &s, Some(&lit)),
+        Err(SerializeError::GeneralizedNotEnabled {
+            location: TermLocation::Graph,
+            ..
+        })
+    ));
+    ser.quad(&s, &s, &lit, Some(&s)).unwrap();
+
+    // Terms inside a triple term are checked as well
+    let mut ser = Serializer::new(RdfStreamOptions {
+        rdf_star: true,
+        ..Serializer::default_options(PhysicalStreamType::Triples)
+    })
+    .expect("valid options");
+    let quoted_literal = Term::Triple(Box::new([lit.clone(), s.clone(), s.clone()]));
+    assert!(matches!(
+        ser.triple(&s, &s, &quoted_literal),
+        Err(SerializeError::GeneralizedNotEnabled {
+            location: TermLocation::Subject,
+            ..
+        })
+    ));
+    ser.triple(&quoted, &s, &quoted).unwrap();
+}
+
+#[test]
+fn stream_versions() {
+    let versioned = |version| {
+        let mut frames = Serializer::new(Serializer::default_options(PhysicalStreamType::Triples))
+            .unwrap()
+            .finish();
+        let Some(Row::Options(options)) = &mut frames[0].rows[0].row else {
+            panic!("stream starts with options");
+        };
+        options.version = version;
+        let mut des = Deserializer::<StringRdf>::new();
+        des.handle_frame(frames.remove(0), &mut Vec::new())
+            .map(|_| des.version())
+    };
+
+    // Streams without a version are read like 1.0.x streams
+    assert_eq!(versioned(0).unwrap(), Some(0));
+    assert_eq!(versioned(1).unwrap(), Some(1));
+    assert_eq!(versioned(2).unwrap(), Some(2));
+    assert!(matches!(
+        versioned(3).as_ref().map_err(DeserializeError::kind),
+        Err(DeserializeError::ConfigError(
+            ConfigError::UnsupportedVersion { .. }
+        ))
+    ));
+
+    assert!(matches!(
+        Serializer::new(RdfStreamOptions {
+            version: 3,
+            ..Serializer::default_options(PhysicalStreamType::Triples)
+        }),
+        Err(ConfigError::UnsupportedVersion {
+            version: 3,
+            supported: 2
+        })
+    ));
+}
+
+#[test]
+fn repeated_options() {
+    let stream = |options: RdfStreamOptions, i: usize| {
+        let mut ser = Serializer::new(options).unwrap();
+        let s = iri(&format!("http://example.org/s{}", i));
+        ser.triple(&s, &s, &s).unwrap();
+        ser.finish()
+    };
+    let expected = |i: usize| {
+        let s = format!("<http://example.org/s{}>", i);
+        (s.clone(), s.clone(), s, None)
+    };
+
+    // An identical options row in the middle of a stream
+    let mut frames = stream(options(8, 3, 2), 0);
+    let repeated = frames[0].rows[0].clone();
+    frames[0].rows.push(repeated);
+    assert_eq!(decode(frames), vec![expected(0)]);
+
+    let mut concatenated = stream(options(16, 3, 2), 0);
+    concatenated.extend(stream(options(8, 3, 2), 1));
+
+    let mut des = Deserializer::<StringRdf>::new();
+    let mut out = Vec::new();
+    let changed = concatenated
+        .iter()
+        .try_for_each(|frame| des.handle_frame(frame.clone(), &mut out).map(|_| ()));
+    assert!(matches!(
+        changed.as_ref().map_err(DeserializeError::kind),
+        Err(DeserializeError::ConfigError(ConfigError::OptionsChanged(
+            "max_name_table_size"
+        )))
+    ));
+
+    let restarted = |frames: Vec<RdfStreamFrame>| {
+        let mut des = Deserializer::<StringRdf>::new();
+        des.set_stream_restart(true);
+        let mut out = Vec::new();
+        for frame in frames {
+            des.handle_frame(frame, &mut out).expect("valid frame");
+        }
+        out
+    };
+    assert_eq!(restarted(concatenated), vec![expected(0), expected(1)]);
+
+    // Identical options starting a frame after statements start the next file
+    let mut concatenated = stream(options(8, 3, 2), 0);
+    concatenated.extend(stream(options(8, 3, 2), 1));
+    assert_eq!(restarted(concatenated), vec![expected(0), expected(1)]);
+}
+
+#[test]
+fn restart_keeps_tables_for_repeated_options() {
+    let mut ser = Serializer::new(options(8, 3, 2)).unwrap();
+    let a = iri("http://example.org/a");
+    let b = iri("http://example.org/b");
+    ser.triple(&a, &a, &a).unwrap();
+    ser.triple(&b, &a, &a).unwrap();
+    let mut frames = ser.finish();
+
+    // The second triple reuses the prefix entry and repeats the predicate and object of
+    // the first one, it can only be decoded with the lookup state kept
+    let second = frames[0]
+        .rows
+        .iter()
+        .rposition(|row| matches!(row.row, Some(Row::Name(_))))
+        .expect("name entry of the second triple");
+    let repeated = frames[0].rows[0].clone();
+    frames[0].rows.insert(second, repeated);
+
+    let mut des = Deserializer::<StringRdf>::new();
+    des.set_stream_restart(true);
+    let mut out = Vec::new();
+    for frame in frames {
+        des.handle_frame(frame, &mut out).expect("valid frame");
+    }
+    let a = "<http://example.org/a>".to_string();
+    let b = "<http://example.org/b>".to_string();
+    assert_eq!(
+        out,
+        vec![
+            (a.clone(), a.clone(), a.clone(), None),
+            (b, a.clone(), a, None)
+        ]
+    );
+}
@@ -0,0 +1,212 @@
+use std::io::Cursor;
+use std::time::{Duration, UNIX_EPOCH};
+
+use jelly::{
+    FrameReader, FrameWriter,
+    error::{
+        DeserializeError, FrameReadError, LookupError, MessageType, MetadataError, TermLocation,
+    },
+    from_rdf::{LiteralKind, Term},
+    metadata::FrameMetadata,
+    proto::{PhysicalStreamType, RdfStreamFrame, rdf_stream_row::Row, rdf_triple as t},
+    serialize::{FramingPolicy, Serializer},
+    to_rdf::StringRdf,
+};
+
+mod common;
+
+/// [`common::frames`] written to a delimited stream
+fn stream(count: usize) -> Vec<u8> {
+    let mut writer = FrameWriter::new(Vec::new());
+    writer.write_frames(common::frames(count)).unwrap();
+    writer.into_inner().unwrap()
+}
+
+#[test]
+fn statements_across_frames() {
+    let statements: Vec<_> = FrameReader::new(Cursor::new(stream(50)))
+        .statements::<StringRdf>()
+        .collect::<Result<_, _>>()
+        .expect("valid stream");
+    assert_eq!(statements.len(), 50);
+    assert!(statements.iter().all(|(_, graph)| graph.is_none()));
+    assert_eq!(
+        statements[42].0,
+        (
+            "<http://example.org/s42>".to_string(),
+            "<http://example.org/p>".to_string(),
+            "\"42\"".to_string(),
+        )
+    );
+
+    let objects: Vec<_> = FrameReader::new(Cursor::new(stream(50)))
+        .statements::<StringRdf>()
+        .filter_map(Result::ok)
+        .map(|((_, _, o), _)| o)
+        .filter(|o| o.ends_with("7\""))
+        .take(3)
+        .collect();
+    assert_eq!(objects, vec!["\"7\"", "\"17\"", "\"27\""]);
+}
+
+#[test]
+fn statements_end_after_an_error() {
+    let mut bytes = stream(50);
+    bytes.truncate(bytes.len() - 1);
+
+    let mut statements = FrameReader::new(Cursor::new(bytes)).statements::<StringRdf>();
+    let read = statements.by_ref().take_while(Result::is_ok).count();
+    assert!(read > 0 && read < 50);
+    assert!(statements.next().is_none());
+
+    let mut bytes = stream(50);
+    bytes.truncate(bytes.len() - 1);
+    let error = FrameReader::new(Cursor::new(bytes))
+        .statements::<StringRdf>()
+        .find_map(Result::err);
+    assert!(matches!(
+        error,
+        Some(DeserializeError::FrameRead(
+            FrameReadError::TruncatedFrame { .. }
+        ))
+    ));
+}
+
+#[test]
+fn frame_metadata() {
+    let created = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+    let mut ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Triples))
+        .expect("valid options");
+    for i in 0..3u64 {
+        let s = Term::Iri(format!("http://example.org/s{}", i));
+        ser.triple(&s, &s, &s).unwrap();
+        let metadata = ser.frame_metadata_mut();
+        metadata.insert_u64("seq", i);
+        metadata.insert_str("source", "sensor");
+        metadata.insert_timestamp("created", created);
+        ser.flush();
+    }
+    let mut writer = FrameWriter::new(Vec::new());
+    writer.write_frames(ser.finish()).unwrap();
+
+    let mut statements =
+        FrameReader::new(Cursor::new(writer.into_inner().unwrap())).statements::<StringRdf>();
+    for i in 0..3u64 {
+        statements.next().unwrap().expect("valid statement");
+        let metadata = statements.metadata();
+        assert_eq!(metadata.get_u64("seq").unwrap(), Some(i));
+        assert_eq!(metadata.get_str("source").unwrap(), Some("sensor"));
+        assert_eq!(metadata.get_timestamp("created").unwrap(), Some(created));
+        assert_eq!(metadata.get_u64("missing").unwrap(), None);
+        assert!(matches!(
+            metadata.get_u64("source"),
+            Err(MetadataError::InvalidLength { len: 6, .. })
+        ));
+    }
+    assert!(statements.next().is_none());
+
+    let mut metadata = std::collections::HashMap::new();
+    let before_epoch = UNIX_EPOCH - Duration::from_secs(60);
+    metadata.insert_timestamp("t", before_epoch);
+    assert_eq!(metadata.get_timestamp("t").unwrap(), Some(before_epoch));
+    metadata.insert("t".to_string(), vec![0xff]);
+    assert!(matches!(
+        metadata.get_str("t"),
+        Err(MetadataError::InvalidUtf8 { .. })
+    ));
+}
+
+#[test]
+fn metadata_of_frames_without_statements() {
+    let mut ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Triples))
+        .expect("valid options");
+    for i in 0..2u64 {
+        let s = Term::Iri(format!("http://example.org/s{}", i));
+        ser.triple(&s, &s, &s).unwrap();
+        ser.frame_metadata_mut().insert_u64("seq", i);
+        ser.flush();
+    }
+    let metadata_only = |key: &str| {
+        let mut frame = RdfStreamFrame::default();
+        frame.metadata.insert_str(key, "x");
+        frame
+    };
+    let mut frames = ser.finish();
+    frames.insert(1, metadata_only("checkpoint"));
+    frames.push(metadata_only("end"));
+
+    let mut writer = FrameWriter::new(Vec::new());
+    writer.write_frames(frames).unwrap();
+    let mut statements =
+        FrameReader::new(Cursor::new(writer.into_inner().unwrap())).statements::<StringRdf>();
+
+    statements.next().unwrap().expect("valid statement");
+    let metadata = statements.metadata();
+    assert_eq!(metadata.get_u64("seq").unwrap(), Some(0));
+    assert_eq!(metadata.len(), 1);
+
+    statements.next().unwrap().expect("valid statement");
+    let metadata = statements.metadata();
+    assert_eq!(metadata.get_u64("seq").unwrap(), Some(1));
+    assert_eq!(metadata.get_str("checkpoint").unwrap(), Some("x"));
+
+    assert!(statements.next().is_none());
+    let metadata = statements.metadata();
+    assert_eq!(metadata.get_str("end").unwrap(), Some("x"));
+    assert_eq!(metadata.len(), 1);
+}
+
+#[test]
+fn error_positions() {
+    let mut ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Triples))
+        .expect("valid options");
+    ser.set_framing_policy(FramingPolicy::Rows(8));
+    for i in 0..20 {
+        let s = Term::Iri(format!("http://example.org/s{}", i));
+        let o = Term::Literal(i.to_string(), LiteralKind::Simple);
+        ser.triple(&s, &s, &o).unwrap();
+    }
+    let mut frames = ser.finish();
+
+    // Point the predicate of the last triple in the third frame to a missing name
+    let (row, triple) = frames[2]
+        .rows
+        .iter_mut()
+        .enumerate()
+        .rev()
+        .find_map(|(i, row)| match &mut row.row {
+            Some(Row::Triple(triple)) => Some((i, triple)),
+            _ => None,
+        })
+        .expect("frame with a triple");
+    let Some(t::Predicate::PIri(iri)) = &mut triple.predicate else {
+        panic!("triple with a predicate IRI");
+    };
+    iri.name_id = 4000;
+
+    let mut writer = FrameWriter::new(Vec::new());
+    writer.write_frames(frames[..2].iter().cloned()).unwrap();
+    writer.flush().unwrap();
+    let offset = writer.get_ref().len() as u64;
+    writer.write_frames(frames.into_iter().skip(2)).unwrap();
+
+    let error = FrameReader::new(Cursor::new(writer.into_inner().unwrap()))
+        .statements::<StringRdf>()
+        .find_map(Result::err)
+        .expect("invalid stream");
+    let position = error.position().expect("error with position");
+    assert_eq!(position.frame, 2);
+    assert_eq!(position.row, Some(row));
+    assert_eq!(position.row_kind, Some(MessageType::Triple));
+    assert_eq!(position.location, Some(TermLocation::Predicate));
+    assert_eq!(position.offset, Some(offset));
+    assert!(matches!(
+        error.kind(),
+        DeserializeError::LookupError(LookupError::Missing(..))
+    ));
+    assert!(
+        error
+            .to_string()
+            .contains(&format!("frame 2 (byte {})", offset))
+    );
+}
@@ -0,0 +1,252 @@
+#![cfg(feature = "value")]
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use jelly::{
+    deserialize::{Deserializer, TryRdfHandler},
+    error::{DeserializeError, ValueError},
+    from_rdf::{LiteralKind, Term},
+    proto::{PhysicalStreamType, RdfStreamFrame},
+    serialize::Serializer,
+    to_rdf::{SharedRdf, SharedTerm, StringRdf},
+    value::{Value, XsdType},
+};
+use num_bigint::BigInt;
+
+fn parse(lex: &str, ty: XsdType) -> Result<Value, ValueError> {
+    Value::parse_as(lex, ty)
+}
+
+#[test]
+fn numbers_and_booleans() {
+    assert_eq!(parse("-042", XsdType::Integer), Ok(Value::Integer(-42)));
+    assert_eq!(parse("+7", XsdType::Integer), Ok(Value::Integer(7)));
+    let big = "123456789012345678901234567890";
+    assert_eq!(
+        parse(big, XsdType::Integer),
+        Ok(Value::BigInteger(big.parse::<BigInt>().unwrap()))
+    );
+
+    let Ok(Value::Decimal(decimal)) = parse("-12.3400", XsdType::Decimal) else {
+        panic!("valid decimal");
+    };
+    assert_eq!(decimal.scale(), 2);
+    assert_eq!(decimal.to_string(), "-12.34");
+    assert_eq!(decimal.to_f64(), -12.34);
+    let Ok(Value::Decimal(decimal)) = parse(".05", XsdType::Decimal) else {
+        panic!("valid decimal");
+    };
+    assert_eq!(decimal.to_string(), "0.05");
+    assert_eq!(parse("3.", XsdType::Decimal).unwrap().as_f64(), Some(3.0));
+
+    assert_eq!(parse("1.5E3", XsdType::Double), Ok(Value::Double(1500.0)));
+    assert_eq!(
+        parse("-INF", XsdType::Double),
+        Ok(Value::Double(f64::NEG_INFINITY))
+    );
+    assert!(matches!(parse("NaN", XsdType::Double), Ok(Value::Double(d)) if d.is_nan()));
+    assert_eq!(parse("1", XsdType::Boolean), Ok(Value::Boolean(true)));
+    assert_eq!(parse("false", XsdType::Boolean), Ok(Value::Boolean(false)));
+
+    for (lex, ty) in [
+        ("", XsdType::Integer),
+        ("1.0", XsdType::Integer),
+        ("1_000", XsdType::Integer),
+        (" 1", XsdType::Integer),
+        (".", XsdType::Decimal),
+        ("1e3", XsdType::Decimal),
+        ("inf", XsdType::Double),
+        ("1e", XsdType::Double),
+        ("infinity", XsdType::Double),
+        ("TRUE", XsdType::Boolean),
+    ] {
+        assert_eq!(
+            parse(lex, ty),
+            Err(ValueError::Malformed {
+                datatype: ty,
+                lex: lex.to_string()
+            }),
+            "{:?} as {}",
+            lex,
+            ty
+        );
+    }
+
+    assert_eq!(
+        Value::parse("1", "http://www.w3.org/2001/XMLSchema#integer"),
+        Ok(Some(Value::Integer(1)))
+    );
+    assert_eq!(
+        Value::parse("x", "http://www.w3.org/2001/XMLSchema#string"),
+        Ok(None)
+    );
+}
+
+#[test]
+fn date_times() {
+    let Ok(Value::DateTime(date_time)) = parse("2024-02-29T23:59:59.5-05:30", XsdType::DateTime)
+    else {
+        panic!("valid date time");
+    };
+    assert_eq!(date_time.nanosecond, 500_000_000);
+    assert_eq!(date_time.offset, Some(-330));
+    assert_eq!(date_time.to_string(), "2024-02-29T23:59:59.5-05:30");
+    assert_eq!(
+        date_time.to_system_time(),
+        Some(UNIX_EPOCH + Duration::from_millis(1_709_270_999_500))
+    );
+
+    let Ok(Value::DateTime(local)) = parse("-0044-03-15T12:00:00", XsdType::DateTime) else {
+        panic!("valid date time");
+    };
+    assert_eq!(local.year, -44);
+    assert_eq!(local.to_system_time(), None);
+    assert_eq!(local.to_string(), "-0044-03-15T12:00:00");
+
+    let Ok(Value::DateTime(end_of_day)) = parse("1969-12-31T24:00:00Z", XsdType::DateTime) else {
+        panic!("valid date time");
+    };
+    assert_eq!(end_of_day.to_system_time(), Some(UNIX_EPOCH));
+    assert!(parse("0000-01-01T00:00:00", XsdType::DateTime).is_ok());
+
+    for lex in [
+        "2023-02-29T00:00:00",
+        "2024-13-01T00:00:00",
+        "2024-01-01T23:59:60",
+        "2024-01-01T24:00:01",
+        "2024-01-01T12:00",
+        "24-01-01T12:00:00",
+        "02024-01-01T12:00:00",
+        "-0000-01-01T00:00:00",
+        "2024-01-01T12:00:00+15:00",
+        "2024-01-01T12:00:00.",
+        "2024-01-01 12:00:00",
+    ] {
+        assert!(parse(lex, XsdType::DateTime).is_err(), "{}", lex);
+    }
+}
+
+/// Sums integer objects, malformed literals stop decoding
+#[derive(Default)]
+struct Sum(i64);
+
+impl TryRdfHandler<SharedRdf> for Sum {
+    type Error = ValueError;
+
+    fn try_handle_triple(&mut self, [_, _, o]: [&SharedTerm; 3]) -> Result<(), ValueError> {
+        if let Some(i) = o.value()?.as_ref().and_then(Value::as_i64) {
+            self.0 += i;
+        }
+        Ok(())
+    }
+
+    fn try_handle_quad(
+        &mut self,
+        _: ([&SharedTerm; 3], Option<&SharedTerm>),
+    ) -> Result<(), ValueError> {
+        Ok(())
+    }
+}
+
+#[test]
+fn values_of_decoded_literals() {
+    let mut ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Triples))
+        .expect("valid options");
+    let s = Term::Iri("http://example.org/s".to_string());
+    let integer = |lex: &str| {
+        Term::Literal(
+            lex.to_string(),
+            LiteralKind::Datatype(XsdType::Integer.iri().to_string()),
+        )
+    };
+    for o in [
+        integer("1"),
+        integer("2"),
+        Term::Literal("3".to_string(), LiteralKind::Simple),
+        integer("4"),
+    ] {
+        ser.triple(&s, &s, &o).unwrap();
+    }
+    let frames = ser.finish();
+
+    let mut des = Deserializer::<SharedRdf>::new();
+    let mut sum = Sum::default();
+    for frame in frames {
+        sum = des.handle_frame(frame, sum).expect("valid frame");
+    }
+    assert_eq!(sum.0, 7);
+
+    ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Triples))
+        .expect("valid options");
+    ser.triple(&s, &s, &integer("1")).unwrap();
+    ser.triple(&s, &s, &integer("one")).unwrap();
+    let mut des = Deserializer::<SharedRdf>::new();
+    let error = ser
+        .finish()
+        .into_iter()
+        .find_map(|frame| des.handle_frame(frame, Sum::default()).err())
+        .expect("malformed literal");
+    let DeserializeError::Handler(error) = error.kind() else {
+        panic!("handler error");
+    };
+    assert_eq!(error.to_string(), "\"one\" is not a valid xsd:integer");
+}
+
+/// Frames of triples with these objects, and the values of the objects
+fn typed_objects() -> (Vec<RdfStreamFrame>, Vec<Result<Option<Value>, ValueError>>) {
+    let typed = |lex: &str, ty: XsdType| {
+        Term::Literal(lex.to_string(), LiteralKind::Datatype(ty.iri().to_string()))
+    };
+    let mut ser = Serializer::new(Serializer::default_options(PhysicalStreamType::Triples))
+        .expect("valid options");
+    let s = Term::Iri("http://example.org/s".to_string());
+    for o in [
+        typed("1.50", XsdType::Decimal),
+        typed("1\t2", XsdType::Integer),
+        Term::Literal("1".to_string(), LiteralKind::Language("en".to_string())),
+        s.clone(),
+    ] {
+        ser.triple(&s, &s, &o).unwrap();
+    }
+    let values = vec![
+        Value::parse_as("1.5", XsdType::Decimal).map(Some),
+        Err(ValueError::Malformed {
+            datatype: XsdType::Integer,
+            lex: "1\t2".to_string(),
+        }),
+        Ok(None),
+        Ok(None),
+    ];
+    (ser.finish(), values)
+}
+
+#[test]
+fn values_of_string_terms() {
+    let (frames, expected) = typed_objects();
+    let mut out = Vec::new();
+    let mut des = Deserializer::<StringRdf>::new();
+    for frame in frames {
+        des.handle_frame(frame, &mut out).expect("valid frame");
+    }
+    let values: Vec<_> = out.iter().map(|(_, _, o, _)| StringRdf::value(o)).collect();
+    assert_eq!(values, expected);
+}
+
+#[cfg(feature = "sophia")]
+#[test]
+fn values_of_sophia_terms() {
+    use jelly::to_rdf::{SophiaRdf, ToRdf};
+    use sophia_term::ArcTerm;
+
+    let (frames, expected) = typed_objects();
+    let mut values = Vec::new();
+    let mut handler = (
+        |[_, _, o]: [ArcTerm; 3]| values.push(SophiaRdf::value(&o)),
+        |_: <SophiaRdf as ToRdf>::Quad<'_>| {},
+    );
+    let mut des = Deserializer::<SophiaRdf>::new();
+    for frame in frames {
+        des.handle_frame(frame, &mut handler).expect("valid frame");
+    }
+    assert_eq!(values, expected);
+}